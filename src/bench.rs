@@ -0,0 +1,88 @@
+use crate::framebuffer::Framebuffer;
+use crate::game_of_life::{Engine, GameOfLife, Hue};
+use raylib::prelude::*;
+use std::time::{Duration, Instant};
+
+// Medición de rendimiento: `cargo run --release -- --bench`
+// Cada caso llena el grid con una sopa aleatoria (siempre la misma semilla) y mide
+// cuántas generaciones por segundo calcula update, colores incluidos. La tabla del README
+// compara estos números con los de la versión que reservaba un Vec<Hue> por nacimiento.
+
+const CASES: [(&str, u32, u32, Engine, bool); 7] = [
+    ("240x180, célula por célula, 1 hilo", 240, 180, Engine::PerCell, false),
+    ("1024x1024, célula por célula, 1 hilo", 1024, 1024, Engine::PerCell, false),
+    ("1024x1024, célula por célula, todos los hilos", 1024, 1024, Engine::PerCell, true),
+    ("1024x1024, paralelo por bits, 1 hilo", 1024, 1024, Engine::BitParallel, false),
+    ("1024x1024, paralelo por bits, todos los hilos", 1024, 1024, Engine::BitParallel, true),
+    ("1024x1024, bloques con tabla, 1 hilo", 1024, 1024, Engine::Lookup, false),
+    ("1024x1024, bloques con tabla, todos los hilos", 1024, 1024, Engine::Lookup, true),
+];
+
+// Tiempo mínimo que se mide cada caso
+const MEASURE_TIME: Duration = Duration::from_secs(2);
+
+pub fn run() {
+    println!("=== Rendimiento de update (B3/S23 con colores) ===");
+    for (name, width, height, engine, all_threads) in CASES {
+        let mut game = GameOfLife::new(width, height);
+        game.set_engine(engine);
+        if !all_threads {
+            game.set_threads(1);
+        }
+        seed_soup(&mut game);
+
+        // Unas generaciones de calentamiento para que las capas de color tengan su tamaño
+        for _ in 0..5 {
+            game.update();
+        }
+
+        let start = Instant::now();
+        let mut generations = 0;
+        while start.elapsed() < MEASURE_TIME {
+            game.update();
+            generations += 1;
+        }
+        let elapsed = start.elapsed().as_secs_f64();
+        println!(
+            "{:<48} {:>9.1} generaciones/s ({:.2} ms por generación)",
+            name,
+            generations as f64 / elapsed,
+            elapsed * 1000.0 / generations as f64
+        );
+    }
+
+    // Dibujar un grid de 1000x1000 a escala 1 en un framebuffer del mismo tamaño
+    let mut game = GameOfLife::new(1000, 1000);
+    seed_soup(&mut game);
+    let mut framebuffer = Framebuffer::new(1000, 1000, Color::BLACK);
+    let start = Instant::now();
+    let mut frames = 0;
+    while start.elapsed() < MEASURE_TIME {
+        framebuffer.clear();
+        game.render(&mut framebuffer, 0, 0, 1);
+        frames += 1;
+    }
+    let elapsed = start.elapsed().as_secs_f64();
+    println!(
+        "{:<48} {:>9.1} frames/s ({:.2} ms por frame)",
+        "render 1000x1000, escala 1",
+        frames as f64 / elapsed,
+        elapsed * 1000.0 / frames as f64
+    );
+}
+
+// Sopa aleatoria con densidad 1/3 y un matiz por célula
+fn seed_soup(game: &mut GameOfLife) {
+    let mut state: u64 = 0x2545_F491_4F6C_DD1D;
+    for x in 0..game.width as usize {
+        for y in 0..game.height as usize {
+            state = state
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            if (state >> 33).is_multiple_of(3) {
+                let hue = Hue::new((state >> 40) as f32 / (1u64 << 24) as f32);
+                game.set_cell_with_color(x, y, true, Some(hue));
+            }
+        }
+    }
+}
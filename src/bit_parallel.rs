@@ -0,0 +1,154 @@
+use crate::bitgrid::BitGrid;
+use crate::boundary::Boundary;
+use crate::rule::Rule;
+
+// Motor de conteo paralelo por bits: suma los 8 vecinos de 64 células a la vez con
+// sumadores completos sobre palabras, en lugar de recorrer cada célula. Solo sirve para
+// reglas totalísticas de la vecindad 3x3 (B/S, con o sin estados Generations).
+
+// Resultado de la regla para cada conteo de 0 a 8 vecinos
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BitRule {
+    birth: [bool; 9],
+    survival: [bool; 9],
+}
+
+impl BitRule {
+    // None si la regla necesita la disposición de los vecinos o una vecindad distinta
+    pub fn new(rule: &Rule) -> Option<Self> {
+        if !rule.is_totalistic_moore() || rule.table.is_some() {
+            return None;
+        }
+        Some(BitRule {
+            birth: std::array::from_fn(|count| rule.next_state(false, count as u32)),
+            survival: std::array::from_fn(|count| rule.next_state(true, count as u32)),
+        })
+    }
+
+    // Siguiente estado de una célula según su conteo de vecinos (de 0 a 8)
+    pub fn next_state(&self, alive: bool, count: u32) -> bool {
+        if alive {
+            self.survival[count as usize]
+        } else {
+            self.birth[count as usize]
+        }
+    }
+
+    // Máscaras de las células que nacerían y de las que sobrevivirían según su conteo
+    fn apply(&self, count: [u64; 4]) -> (u64, u64) {
+        let mut birth = 0;
+        let mut survival = 0;
+        for value in 0..9 {
+            if !self.birth[value] && !self.survival[value] {
+                continue;
+            }
+            // Células cuyo conteo de 4 bits es exactamente `value`
+            let equal = (0..4).fold(!0, |mask, bit| {
+                if value >> bit & 1 == 1 {
+                    mask & count[bit]
+                } else {
+                    mask & !count[bit]
+                }
+            });
+            if self.birth[value] {
+                birth |= equal;
+            }
+            if self.survival[value] {
+                survival |= equal;
+            }
+        }
+        (birth, survival)
+    }
+}
+
+// Copiar el grid en `padded` con un borde de una célula leído según la condición de
+// frontera: la célula (x, y) queda en (x + 1, y + 1), así el conteo nunca sale del grid
+// con borde. `padded` se reutiliza entre generaciones y solo se crea si cambia de tamaño.
+pub fn pad(grid: &BitGrid, boundary: Boundary, padded: &mut BitGrid) {
+    let (width, height) = (grid.width(), grid.height());
+    if padded.width() != width + 2 || padded.height() != height + 2 {
+        *padded = BitGrid::new(width + 2, height + 2);
+    } else {
+        padded.clear();
+    }
+
+    for y in 0..height {
+        let source = grid.row(y);
+        let target = padded.row_mut(y + 1);
+        for (k, &word) in source.iter().enumerate() {
+            target[k] |= word << 1;
+            if let Some(next) = target.get_mut(k + 1) {
+                *next |= word >> 63;
+            }
+        }
+    }
+
+    // Celdas del borde
+    let mut set_border = |px: usize, py: usize| {
+        let cell = boundary.resolve(px as i64 - 1, py as i64 - 1, width, height);
+        if cell.is_some_and(|(x, y)| grid.get(x, y)) {
+            padded.set(px, py, true);
+        }
+    };
+    for px in 0..width + 2 {
+        set_border(px, 0);
+        set_border(px, height + 1);
+    }
+    for py in 1..height + 1 {
+        set_border(0, py);
+        set_border(width + 1, py);
+    }
+}
+
+// 64 bits de una fila a partir de la columna `start`
+fn bits_at(row: &[u64], start: usize) -> u64 {
+    let (word, shift) = (start / 64, start % 64);
+    let low = row.get(word).copied().unwrap_or(0) >> shift;
+    if shift == 0 {
+        return low;
+    }
+    low | row.get(word + 1).copied().unwrap_or(0) << (64 - shift)
+}
+
+fn full_adder(a: u64, b: u64, c: u64) -> (u64, u64) {
+    (a ^ b ^ c, (a & b) | (c & (a ^ b)))
+}
+
+fn half_adder(a: u64, b: u64) -> (u64, u64) {
+    (a ^ b, a & b)
+}
+
+// Sumar 8 palabras de vecinos: devuelve el conteo de cada célula en 4 bits (de 0 a 8)
+fn count_neighbors(n: [u64; 8]) -> [u64; 4] {
+    let (ones_a, twos_a) = full_adder(n[0], n[1], n[2]);
+    let (ones_b, twos_b) = full_adder(n[3], n[4], n[5]);
+    let (ones_c, twos_c) = half_adder(n[6], n[7]);
+    let (bit0, twos_d) = full_adder(ones_a, ones_b, ones_c);
+
+    let (twos, fours_a) = full_adder(twos_a, twos_b, twos_c);
+    let (bit1, fours_b) = half_adder(twos, twos_d);
+
+    let (bit2, bit3) = half_adder(fours_a, fours_b);
+    [bit0, bit1, bit2, bit3]
+}
+
+// Calcular la palabra `word` de la fila `y` de la siguiente generación. Devuelve las
+// máscaras de las células que nacerían y de las que sobrevivirían; quien llama decide
+// cuáles pueden nacer (las células muriendo de las reglas Generations no pueden).
+pub fn next_word(padded: &BitGrid, rule: &BitRule, y: usize, word: usize) -> (u64, u64) {
+    // En el grid con borde, la célula x está en la columna x + 1: sus vecinos de la
+    // izquierda empiezan en x, los del centro en x + 1 y los de la derecha en x + 2
+    let start = word * 64;
+    let (above, here, below) = (padded.row(y), padded.row(y + 1), padded.row(y + 2));
+    let count = count_neighbors([
+        bits_at(above, start),
+        bits_at(above, start + 1),
+        bits_at(above, start + 2),
+        bits_at(here, start),
+        bits_at(here, start + 2),
+        bits_at(below, start),
+        bits_at(below, start + 1),
+        bits_at(below, start + 2),
+    ]);
+    rule.apply(count)
+}
@@ -0,0 +1,75 @@
+// Grid de células vivas empaquetado en bits: cada fila ocupa `words_per_row` palabras
+// de 64 bits consecutivas (la célula x está en el bit x % 64 de la palabra x / 64), así
+// un grid de varios millones de células ocupa unos cientos de KB contiguos.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BitGrid {
+    width: usize,
+    height: usize,
+    words_per_row: usize,
+    words: Vec<u64>,
+}
+
+impl BitGrid {
+    pub fn new(width: usize, height: usize) -> Self {
+        let words_per_row = width.div_ceil(64);
+        BitGrid {
+            width,
+            height,
+            words_per_row,
+            words: vec![0; words_per_row * height],
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn words_per_row(&self) -> usize {
+        self.words_per_row
+    }
+
+    // Obtener una célula (las coordenadas deben estar dentro del grid)
+    pub fn get(&self, x: usize, y: usize) -> bool {
+        let word = self.words[y * self.words_per_row + x / 64];
+        (word >> (x % 64)) & 1 == 1
+    }
+
+    // Establecer una célula (las coordenadas deben estar dentro del grid)
+    pub fn set(&mut self, x: usize, y: usize, alive: bool) {
+        let word = &mut self.words[y * self.words_per_row + x / 64];
+        let bit = 1 << (x % 64);
+        if alive {
+            *word |= bit;
+        } else {
+            *word &= !bit;
+        }
+    }
+
+    // Palabras de una fila; los bits después de `width` siempre valen 0
+    pub fn row(&self, y: usize) -> &[u64] {
+        &self.words[y * self.words_per_row..(y + 1) * self.words_per_row]
+    }
+
+    pub fn row_mut(&mut self, y: usize) -> &mut [u64] {
+        &mut self.words[y * self.words_per_row..(y + 1) * self.words_per_row]
+    }
+
+    // Dividir el grid en bandas de `rows` filas consecutivas que se pueden escribir a la vez
+    pub fn bands_mut(&mut self, rows: usize) -> impl Iterator<Item = &mut [u64]> {
+        self.words.chunks_mut((rows * self.words_per_row).max(1))
+    }
+
+    // Matar todas las células
+    pub fn clear(&mut self) {
+        self.words.fill(0);
+    }
+
+    // Número de células vivas
+    pub fn count_ones(&self) -> u32 {
+        self.words.iter().map(|word| word.count_ones()).sum()
+    }
+}
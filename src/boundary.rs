@@ -0,0 +1,83 @@
+use std::fmt;
+
+// Condición de frontera: qué célula se lee cuando un vecino cae fuera del grid
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum Boundary {
+    // Fuera del grid todo está muerto
+    #[default]
+    Dead,
+    // Toroide: los bordes opuestos están pegados
+    Torus,
+    // Botella de Klein: los bordes izquierdo y derecho se pegan normal; al cruzar
+    // el borde superior o inferior la coordenada x se invierte
+    KleinBottle,
+    // Superficie de cruce (plano proyectivo): ambos pares de bordes se pegan invertidos
+    CrossSurface,
+    // Espejo: los bordes reflejan el grid como si hubiera un espejo
+    Mirror,
+}
+
+impl Boundary {
+    pub const ALL: [Boundary; 5] = [
+        Boundary::Dead,
+        Boundary::Torus,
+        Boundary::KleinBottle,
+        Boundary::CrossSurface,
+        Boundary::Mirror,
+    ];
+
+    // Convertir una coordenada posiblemente fuera del grid en la célula que se lee,
+    // o None si la frontera la considera muerta. Funciona con cualquier distancia al
+    // borde, así que sirve también para vecindades de radio mayor que el grid.
+    pub fn resolve(&self, x: i64, y: i64, width: usize, height: usize) -> Option<(usize, usize)> {
+        let (w, h) = (width as i64, height as i64);
+        if w == 0 || h == 0 {
+            return None;
+        }
+        if (0..w).contains(&x) && (0..h).contains(&y) {
+            return Some((x as usize, y as usize));
+        }
+
+        // Cantidad de veces que se cruzó cada par de bordes
+        let (wraps_x, wraps_y) = (x.div_euclid(w), y.div_euclid(h));
+        let (wx, wy) = (x.rem_euclid(w), y.rem_euclid(h));
+        let flip = |value: i64, size: i64, wraps: i64| {
+            if wraps % 2 != 0 {
+                size - 1 - value
+            } else {
+                value
+            }
+        };
+
+        let (rx, ry) = match self {
+            Boundary::Dead => return None,
+            Boundary::Torus => (wx, wy),
+            Boundary::KleinBottle => (flip(wx, w, wraps_y), wy),
+            Boundary::CrossSurface => (flip(wx, w, wraps_y), flip(wy, h, wraps_x)),
+            // Cada cruce refleja: -1 lee 0, -2 lee 1, width lee width - 1...
+            Boundary::Mirror => (flip(wx, w, wraps_x), flip(wy, h, wraps_y)),
+        };
+        Some((rx as usize, ry as usize))
+    }
+
+    // Indica si la frontera pega bordes opuestos (lo que pasa en un borde afecta al otro)
+    pub fn wraps(&self) -> bool {
+        matches!(
+            self,
+            Boundary::Torus | Boundary::KleinBottle | Boundary::CrossSurface
+        )
+    }
+}
+
+impl fmt::Display for Boundary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Boundary::Dead => "bordes muertos",
+            Boundary::Torus => "toroide",
+            Boundary::KleinBottle => "botella de Klein",
+            Boundary::CrossSurface => "superficie de cruce",
+            Boundary::Mirror => "espejo",
+        };
+        write!(f, "{}", name)
+    }
+}
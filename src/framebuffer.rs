@@ -4,51 +4,143 @@ pub struct Framebuffer {
     pub width: u32,
     pub height: u32,
     pub background_color: Color,
-    pub current_color: Color,
-    pub color_buffer: Image,
+    pixels: Vec<u8>, // RGBA por filas, 4 bytes por píxel
+    texture: Option<Texture2D>, // Se crea en el primer swap_buffer (necesita la ventana) y se reutiliza
+    texture_failed: bool, // Ya se informó un error de la textura: no se reintenta en cada frame
 }
 
 impl Framebuffer {
     pub fn new(width: u32, height: u32, background_color: Color) -> Self {
-        let color_buffer = Image::gen_image_color(width as i32, height as i32, background_color);
-
-        Framebuffer {
+        let mut framebuffer = Framebuffer {
             width,
             height,
             background_color,
-            current_color: Color::WHITE,
-            color_buffer,
-        }
+            pixels: vec![0; width as usize * height as usize * 4],
+            texture: None,
+            texture_failed: false,
+        };
+        framebuffer.clear();
+        framebuffer
     }
-    //limpiar el framebuffer
+    //limpiar el framebuffer (rellena el buffer existente, sin crear otro)
     pub fn clear(&mut self) {
-        self.color_buffer =
-            Image::gen_image_color(self.width as i32, self.height as i32, self.background_color);
+        let background = [
+            self.background_color.r,
+            self.background_color.g,
+            self.background_color.b,
+            self.background_color.a,
+        ];
+        for pixel in self.pixels.chunks_exact_mut(4) {
+            pixel.copy_from_slice(&background);
+        }
+    }
+    //píxeles RGBA por filas, 4 bytes por píxel, para escribirlos directamente
+    pub fn pixels(&self) -> &[u8] {
+        &self.pixels
+    }
+    pub fn pixels_mut(&mut self) -> &mut [u8] {
+        &mut self.pixels
     }
-    //poner píxel en la pantalla
-    pub fn set_pixel(&mut self, x: u32, y: u32) {
-        if x < self.width && y < self.height {
-            self.color_buffer
-                .draw_pixel(x as i32, y as i32, self.current_color);
+    //rellenar un rectángulo con un color (la parte fuera del framebuffer se recorta)
+    pub fn fill_rect(&mut self, x: u32, y: u32, width: u32, height: u32, color: Color) {
+        let right = x.saturating_add(width).min(self.width);
+        let bottom = y.saturating_add(height).min(self.height);
+        if x >= right || y >= bottom {
+            return;
+        }
+        let bytes = [color.r, color.g, color.b, color.a];
+        for row in y..bottom {
+            let start = self.byte_index(x, row);
+            let end = self.byte_index(right, row);
+            for pixel in self.pixels[start..end].chunks_exact_mut(4) {
+                pixel.copy_from_slice(&bytes);
+            }
+        }
+    }
+    //copiar una imagen de `width` x `height` colores (por filas) en la posición dada
+    pub fn blit(&mut self, x: u32, y: u32, width: u32, height: u32, source: &[Color]) {
+        self.blit_scaled(x, y, width, height, source, 1);
+    }
+    //copiar una imagen ampliada: cada color de `source` ocupa un cuadrado de `scale` x `scale`
+    pub fn blit_scaled(&mut self, x: u32, y: u32, width: u32, height: u32, source: &[Color], scale: u32) {
+        let scale = scale.max(1);
+        let right = x.saturating_add(width.saturating_mul(scale)).min(self.width);
+        if width == 0 || x >= right {
+            return;
+        }
+        let visible = ((right - x).div_ceil(scale)) as usize;
+        for (row, colors) in source.chunks_exact(width as usize).take(height as usize).enumerate() {
+            let top = y.saturating_add(row as u32 * scale);
+            if top >= self.height {
+                break;
+            }
+
+            // La primera fila de píxeles se escribe color por color y las demás se copian de ella
+            let start = self.byte_index(x, top);
+            let end = self.byte_index(right, top);
+            let line = &mut self.pixels[start..end];
+            for (pixels, color) in line.chunks_mut(scale as usize * 4).zip(&colors[..visible]) {
+                for pixel in pixels.chunks_exact_mut(4) {
+                    pixel.copy_from_slice(&[color.r, color.g, color.b, color.a]);
+                }
+            }
+            for copy in top + 1..(top + scale).min(self.height) {
+                let target = self.byte_index(x, copy);
+                self.pixels.copy_within(start..end, target);
+            }
         }
     }
+    fn byte_index(&self, x: u32, y: u32) -> usize {
+        (y as usize * self.width as usize + x as usize) * 4
+    }
     //setear el color de fondo
     pub fn set_background_color(&mut self, color: Color) {
         self.background_color = color;
     }
-    //settear el color
-    pub fn set_current_color(&mut self, color: Color) {
-        self.current_color = color;
-    }
-    //guardar el framebuffer en un archivo usando export
+    //guardar el framebuffer en un archivo usando export. GenImageColor crea una imagen
+    //RGBA de 8 bits por canal, con el mismo orden que `pixels`, así se copia de una vez
     pub fn render_to_file(&self, file_path: &str) {
-        self.color_buffer.export_image(file_path);
+        if self.pixels.is_empty() {
+            return;
+        }
+        let image = Image::gen_image_color(self.width as i32, self.height as i32, self.background_color);
+        unsafe {
+            std::slice::from_raw_parts_mut(image.data as *mut u8, self.pixels.len()).copy_from_slice(&self.pixels);
+        }
+        image.export_image(file_path);
     }
-    pub fn swap_buffer(& self, window: &mut RaylibHandle, raylib_thread: &RaylibThread) {
-        if let Ok(texture) = window.load_texture_from_image(raylib_thread, &self.color_buffer) {
-            let mut render = window.begin_drawing(raylib_thread);
-            render.draw_texture(&texture, 0, 0, Color::WHITE)
+    //subir los píxeles a la textura (se crea una sola vez) y dibujarla. El frame se dibuja
+    //siempre: si la textura falla solo queda el fondo y el error se informa una vez
+    pub fn swap_buffer(&mut self, window: &mut RaylibHandle, raylib_thread: &RaylibThread) {
+        if self.texture.is_none() && !self.texture_failed {
+            let image = Image::gen_image_color(self.width as i32, self.height as i32, self.background_color);
+            match window.load_texture_from_image(raylib_thread, &image) {
+                Ok(texture) => self.texture = Some(texture),
+                Err(err) => {
+                    eprintln!("No se pudo crear la textura del framebuffer: {err}");
+                    self.texture_failed = true;
+                }
+            }
+        }
+        let mut uploaded = false;
+        if let Some(texture) = &mut self.texture {
+            match texture.update_texture(&self.pixels) {
+                Ok(()) => uploaded = true,
+                Err(err) if !self.texture_failed => {
+                    eprintln!("No se pudo actualizar la textura del framebuffer: {err}");
+                    self.texture_failed = true;
+                }
+                Err(_) => {}
+            }
+        }
+
+        let mut render = window.begin_drawing(raylib_thread);
+        render.clear_background(self.background_color);
+        if let Some(texture) = &self.texture
+            && uploaded
+        {
+            render.draw_texture(texture, 0, 0, Color::WHITE);
         }
     }
-    
-}
\ No newline at end of file
+
+}
@@ -1,6 +1,19 @@
 use raylib::prelude::*;
+use crate::bit_parallel::{self, BitRule};
+use crate::bitgrid::BitGrid;
+use crate::boundary::Boundary;
 use crate::framebuffer::Framebuffer;
+use crate::inheritance::{CircularMean, ColorInheritance, Parent};
+use crate::kernel::Kernel;
+use crate::lookup::{self, BlockTable};
+use crate::palette::Palette;
+use crate::random;
+use crate::rule::{Neighborhood, Rule, RuleParseError};
+use crate::summed_area::SummedAreaTable;
+use std::collections::HashMap;
+use std::hash::{BuildHasherDefault, Hasher};
 use std::f32::consts::PI;
+use std::sync::Arc;
 
 // Estructura para representar un color basado en matiz (hue)
 #[derive(Clone, Copy, Debug)]
@@ -15,28 +28,52 @@ impl Hue {
         }
     }
 
-    pub fn random() -> Self {
-        use std::collections::hash_map::DefaultHasher;
-        use std::hash::{Hash, Hasher};
-        use std::time::SystemTime;
-        
-        let time = SystemTime::now()
-            .duration_since(SystemTime::UNIX_EPOCH)
-            .unwrap()
-            .as_nanos();
-        
-        let mut hasher = DefaultHasher::new();
-        time.hash(&mut hasher);
-        let hash = hasher.finish();
-        
-        Hue::new((hash as f32) / (u64::MAX as f32))
+    pub fn to_color(self) -> Color {
+        self.to_color_with_value(1.0)
+    }
+
+    // Versión atenuada del color (usada por las células que están muriendo)
+    pub fn to_color_with_value(self, value: f32) -> Color {
+        Hsv::new(self, 1.0, value).to_color()
+    }
+}
+
+impl std::ops::Add for Hue {
+    type Output = Self;
+    
+    fn add(self, other: Self) -> Self {
+        Hue::new(self.value + other.value)
+    }
+}
+
+// Color completo de una célula en HSV: matiz, saturación y brillo (value), los dos
+// últimos entre 0.0 y 1.0. Un Hue solo es el color con saturación y brillo completos.
+#[derive(Clone, Copy, Debug)]
+pub struct Hsv {
+    pub hue: Hue,
+    pub saturation: f32,
+    pub value: f32,
+}
+
+impl Hsv {
+    pub fn new(hue: Hue, saturation: f32, value: f32) -> Self {
+        Hsv {
+            hue,
+            saturation: saturation.clamp(0.0, 1.0),
+            value: value.clamp(0.0, 1.0),
+        }
     }
 
-    pub fn to_color(&self) -> Color {
-        // Convertir hue a RGB usando el algoritmo HSV
-        let h = self.value * 6.0;
-        let c = 1.0; // Saturación
-        let v = 1.0; // Valor
+    // El mismo color con el brillo multiplicado por `factor`
+    pub fn dimmed(&self, factor: f32) -> Self {
+        Hsv::new(self.hue, self.saturation, self.value * factor)
+    }
+
+    pub fn to_color(self) -> Color {
+        // Convertir a RGB usando el algoritmo HSV
+        let h = self.hue.value * 6.0;
+        let v = self.value; // Valor
+        let c = v * self.saturation;
         
         let x = c * (1.0 - (h.rem_euclid(2.0) - 1.0).abs());
         let m = v - c;
@@ -64,32 +101,271 @@ impl Hue {
     }
 }
 
-impl std::ops::Add for Hue {
-    type Output = Self;
-    
-    fn add(self, other: Self) -> Self {
-        Hue::new(self.value + other.value)
+impl From<Hue> for Hsv {
+    fn from(hue: Hue) -> Self {
+        Hsv::new(hue, 1.0, 1.0)
+    }
+}
+
+// Color guardado de una célula: el color HSV, el vector unitario del matiz y el color
+// RGB ya calculados, así promediar los colores de los vecinos (por matiz o por canales
+// RGB) no necesita trigonometría ni convertir el color
+#[derive(Clone, Copy, Debug)]
+pub struct CellColor {
+    pub hsv: Hsv,
+    pub color: Color,
+    cos: f32,
+    sin: f32,
+}
+
+impl CellColor {
+    pub fn new(hsv: Hsv) -> Self {
+        let angle = hsv.hue.value * 2.0 * PI;
+        CellColor { hsv, color: hsv.to_color(), cos: angle.cos(), sin: angle.sin() }
+    }
+    // Vector unitario del matiz (coseno, seno)
+    pub fn unit(&self) -> (f32, f32) {
+        (self.cos, self.sin)
+    }
+}
+
+// Suma de colores: vectores unitarios de los matices más saturación y brillo. Promedia
+// los colores de los vecinos en la pila, sin guardar la lista de colores.
+#[derive(Default)]
+pub struct ColorSum {
+    x: f32,
+    y: f32,
+    saturation: f32,
+    value: f32,
+    weight: u32,
+}
+
+impl ColorSum {
+    pub fn add(&mut self, color: &CellColor, weight: u32) {
+        self.x += weight as f32 * color.cos;
+        self.y += weight as f32 * color.sin;
+        self.saturation += weight as f32 * color.hsv.saturation;
+        self.value += weight as f32 * color.hsv.value;
+        self.weight += weight;
+    }
+
+    // Promedio circular del matiz y promedio de la saturación y el brillo (None si no
+    // se sumó ningún color)
+    pub fn average(&self) -> Option<Hsv> {
+        (self.weight > 0).then(|| {
+            let weight = self.weight as f32;
+            Hsv::new(
+                Hue::new(self.y.atan2(self.x) / (2.0 * PI)),
+                self.saturation / weight,
+                self.value / weight,
+            )
+        })
+    }
+
+    // Peso total de los colores sumados
+    pub fn weight(&self) -> u32 {
+        self.weight
+    }
+}
+
+// Hasher para las claves de la capa de colores: las claves ya son índices distintos,
+// así que los bits bajos (los que eligen la posición en la tabla) son el índice tal
+// cual y las células vecinas de una fila quedan juntas en memoria; solo los 7 bits
+// altos (los que la tabla compara al buscar) se mezclan
+#[derive(Default)]
+pub struct IndexHasher(u64);
+
+impl Hasher for IndexHasher {
+    fn finish(&self) -> u64 {
+        self.0
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.0 = (self.0.rotate_left(8) ^ byte as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15);
+        }
+    }
+
+    fn write_usize(&mut self, value: usize) {
+        let mixed = (value as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15);
+        self.0 = (value as u64 & (u64::MAX >> 7)) | (mixed & !(u64::MAX >> 7));
     }
 }
 
+// Capa de colores: solo las células vivas o muriendo tienen entrada, con la clave
+// y * width + x (el mismo orden por filas que el grid de bits)
+pub type ColorLayer = HashMap<usize, CellColor, BuildHasherDefault<IndexHasher>>;
+
+// Forma de calcular cada generación
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum Engine {
+    // Célula por célula: sirve para todas las reglas, kernels y vecindades
+    #[default]
+    PerCell,
+    // 64 células a la vez con sumadores sobre palabras; solo para reglas totalísticas
+    // de la vecindad 3x3 sin kernel (las demás siguen usando PerCell)
+    BitParallel,
+    // Bloques de 4x4 células con una tabla precalculada de su vecindad 6x6 (al estilo
+    // QuickLife); sirve para las mismas reglas que BitParallel
+    Lookup,
+}
+
+// Qué representa el color con el que se dibuja cada célula
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum ColorMode {
+    // El color heredado de cada célula, con el matiz pasado por la paleta (por defecto)
+    #[default]
+    Inherited,
+    // El matiz heredado pasado por la paleta (con el brillo de la célula)
+    Hue,
+    // La paleta según la edad, de recién nacida (la última parada) a muy vieja (la primera)
+    Age,
+    // El color heredado, más oscuro cuanto más vieja es la célula
+    AgeShaded,
+    // La paleta según el estado: viva (la última parada) y muriendo, hacia la primera
+    State,
+}
+
+// Cuánto se oscurecen en el modo AgeShaded las células de edad age_scale o más
+const AGE_SHADE: f32 = 0.7;
+
+impl ColorMode {
+    // Color de una célula viva o muriendo (lo comparten GameOfLife y SparseLife). `fade` es
+    // el brillo según el estado (1.0 = viva) y `age_fraction` la posición de su edad en el
+    // degradado, que solo se calcula en los modos por edad.
+    pub fn cell_color(
+        self,
+        palette: &Palette,
+        color: Option<&CellColor>,
+        fade: f32,
+        age_fraction: impl FnOnce() -> f32,
+    ) -> Color {
+        match self {
+            ColorMode::Inherited => match color {
+                Some(color) => palette.sample_hsv(color.hsv.dimmed(fade)),
+                None => scale_color(palette.alive, fade),
+            },
+            ColorMode::Hue => match color {
+                Some(color) => scale_color(palette.sample_hue(color.hsv.hue), color.hsv.value * fade),
+                None => scale_color(palette.alive, fade),
+            },
+            ColorMode::Age => scale_color(palette.sample(1.0 - age_fraction()), fade),
+            ColorMode::AgeShaded => {
+                let shade = (1.0 - AGE_SHADE * age_fraction()) * fade;
+                match color {
+                    Some(color) => palette.sample_hsv(color.hsv.dimmed(shade)),
+                    None => scale_color(palette.alive, shade),
+                }
+            }
+            ColorMode::State => palette.sample(fade),
+        }
+    }
+}
+
+// Posición de una edad en el degradado, de 0.0 (recién nacida) a 1.0 (edad `scale` o más),
+// en escala logarítmica para distinguir las edades jóvenes
+pub fn age_fraction(age: u64, scale: u64) -> f32 {
+    let scale = scale.max(1);
+    (age.min(scale) as f32).ln_1p() / (scale as f32).ln_1p()
+}
+
+// Cómo calculan la siguiente generación de cada palabra los motores que trabajan sobre
+// el grid de bits
+enum WordStep<'a> {
+    Bits(&'a BitRule),
+    Blocks(&'a BlockTable),
+}
+
+// Lado de los bloques con los que se sigue qué zonas del grid cambian. Coincide con el
+// ancho de una palabra del grid de bits: cada fila de un bloque es una palabra.
+pub const TILE_SIZE: usize = 64;
+
 pub struct GameOfLife {
     pub width: u32,
     pub height: u32,
-    pub current_generation: Vec<Vec<bool>>,
-    pub next_generation: Vec<Vec<bool>>,
-    pub current_colors: Vec<Vec<Option<Hue>>>,
-    pub next_colors: Vec<Vec<Option<Hue>>>,
-    pub alive_color: Color,
-    pub dead_color: Color,
-    pub color_variation: f32,
+    pub current_generation: BitGrid,
+    pub next_generation: BitGrid,
+    pub current_colors: ColorLayer,
+    pub next_colors: ColorLayer,
+    pub current_decay: Vec<u8>, // Por filas: 0 = viva o muerta, k > 0 = k-ésimo estado de decaimiento
+    pub next_decay: Vec<u8>,
+    pub born: Vec<u64>, // Por filas: generación en la que nació cada célula viva o muriendo
+    pub palette: Palette, // Fondo, color de las células sin color y degradado de los modos de color
+    pub color_mode: ColorMode,
+    pub age_scale: u64, // Edad a la que los modos por edad llegan al final del degradado
+    pub color_variation: f32, // Variación del matiz de las células que nacen
+    pub saturation_variation: f32, // Variación de la saturación
+    pub value_variation: f32, // Variación del brillo
+    pub rule: Rule,
+    pub kernel: Option<Kernel>, // Vecindad personalizada que reemplaza la de la regla
+    pub boundary: Boundary,
+    pub threads: usize, // Hilos usados por update (1 = secuencial)
+    pub engine: Engine,
+    pub inheritance: Arc<dyn ColorInheritance>, // Cómo hereda el color una célula que nace
+    pub generation: u64, // Generaciones calculadas desde la creación del grid
+    seed: u64, // Semilla de los colores al azar y de la variación de color
+    pub active_tiles: Vec<bool>, // Bloques de TILE_SIZE x TILE_SIZE que se recalculan en el siguiente paso
+    pub changed_cells: BitGrid, // Células que cambiaron de estado o color desde el último clear_changes
+    repaint_all: bool, // El próximo render_changes redibuja todo el grid
+    band_colors: Vec<Vec<(usize, CellColor)>>, // Colores calculados por cada banda, reutilizados entre pasos
+    padded: BitGrid, // Grid con borde del motor paralelo por bits, reutilizado entre pasos
+    block_table: Option<BlockTable>, // Tabla del motor Lookup para la última regla usada
+}
+
+// Copia de la simulación (el Scheduler copia una en cada frame para dibujarla). Los
+// buffers de la siguiente generación y los de trabajo de update no se copian: update los
+// sobrescribe antes de usarlos.
+impl Clone for GameOfLife {
+    fn clone(&self) -> Self {
+        let mut copy = GameOfLife::with_seed(self.width, self.height, self.seed);
+        copy.clone_from(self);
+        copy
+    }
+
+    // Reutiliza la memoria de `self` si el grid tiene el mismo tamaño
+    fn clone_from(&mut self, source: &Self) {
+        if (self.width, self.height) != (source.width, source.height) {
+            *self = GameOfLife::with_seed(source.width, source.height, source.seed);
+        }
+        self.current_generation.clone_from(&source.current_generation);
+        self.current_colors.clone_from(&source.current_colors);
+        self.current_decay.clone_from(&source.current_decay);
+        self.born.clone_from(&source.born);
+        self.palette.clone_from(&source.palette);
+        self.color_mode = source.color_mode;
+        self.age_scale = source.age_scale;
+        self.color_variation = source.color_variation;
+        self.saturation_variation = source.saturation_variation;
+        self.value_variation = source.value_variation;
+        self.rule.clone_from(&source.rule);
+        self.kernel.clone_from(&source.kernel);
+        self.boundary = source.boundary;
+        self.threads = source.threads;
+        self.engine = source.engine;
+        self.inheritance.clone_from(&source.inheritance);
+        self.generation = source.generation;
+        self.seed = source.seed;
+        self.active_tiles.clone_from(&source.active_tiles);
+        self.changed_cells.clone_from(&source.changed_cells);
+        self.repaint_all = source.repaint_all;
+    }
 }
 
 impl GameOfLife {
     pub fn new(width: u32, height: u32) -> Self {
-        let current_generation = vec![vec![false; height as usize]; width as usize];
-        let next_generation = vec![vec![false; height as usize]; width as usize];
-        let current_colors = vec![vec![None; height as usize]; width as usize];
-        let next_colors = vec![vec![None; height as usize]; width as usize];
+        Self::with_seed(width, height, random::time_seed())
+    }
+
+    // Crear el grid con una semilla fija: la misma semilla y las mismas acciones dan
+    // exactamente la misma simulación
+    pub fn with_seed(width: u32, height: u32, seed: u64) -> Self {
+        let cells = width as usize * height as usize;
+        let current_generation = BitGrid::new(width as usize, height as usize);
+        let next_generation = BitGrid::new(width as usize, height as usize);
+        let current_colors = ColorLayer::default();
+        let next_colors = ColorLayer::default();
+        let current_decay = vec![0; cells];
+        let next_decay = vec![0; cells];
         
         GameOfLife {
             width,
@@ -98,87 +374,137 @@ impl GameOfLife {
             next_generation,
             current_colors,
             next_colors,
-            alive_color: Color::WHITE,
-            dead_color: Color::BLACK,
+            current_decay,
+            next_decay,
+            born: vec![0; cells],
+            palette: Palette::default(),
+            color_mode: ColorMode::Inherited,
+            age_scale: 1000,
             color_variation: 0.05, // Variación de color por defecto
+            saturation_variation: 0.0,
+            value_variation: 0.0,
+            rule: Rule::conway(),
+            kernel: None,
+            boundary: Boundary::Dead,
+            threads: std::thread::available_parallelism().map_or(1, |n| n.get()),
+            engine: Engine::PerCell,
+            inheritance: Arc::new(CircularMean),
+            generation: 0,
+            seed,
+            active_tiles: vec![true; (width as usize).div_ceil(TILE_SIZE) * (height as usize).div_ceil(TILE_SIZE)],
+            changed_cells: BitGrid::new(width as usize, height as usize),
+            repaint_all: true,
+            band_colors: Vec::new(),
+            padded: BitGrid::new(0, 0),
+            block_table: None,
         }
     }
 
+    // Semilla de los números al azar
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
     // Limpiar todo el grid
     pub fn clear_grid(&mut self) {
-        for x in 0..self.width as usize {
-            for y in 0..self.height as usize {
-                self.current_generation[x][y] = false;
-                self.current_colors[x][y] = None;
-            }
-        }
+        self.current_generation.clear();
+        self.current_colors.clear();
+        self.current_decay.fill(0);
+        self.activate_all();
+        self.invalidate_render();
+    }
+
+    // Posición de una célula en la capa de colores y en la de decaimiento
+    fn index(&self, x: usize, y: usize) -> usize {
+        y * self.width as usize + x
     }
 
     // Establecer el estado de una célula específica
     pub fn set_cell(&mut self, x: usize, y: usize, alive: bool) {
-        if x < self.width as usize && y < self.height as usize {
-            self.current_generation[x][y] = alive;
-            if alive {
-                self.current_colors[x][y] = Some(Hue::random());
-            } else {
-                self.current_colors[x][y] = None;
-            }
-        }
+        // Matiz al azar según la semilla, la generación y la posición
+        let hue = alive.then(|| Hue::new(random::unit(self.cell_random(x, y, random::STREAM_SET_CELL))));
+        self.set_cell_with_color(x, y, alive, hue);
     }
 
     // Establecer una célula con color específico
     pub fn set_cell_with_color(&mut self, x: usize, y: usize, alive: bool, hue: Option<Hue>) {
+        self.set_cell_with_hsv(x, y, alive, hue.map(Hsv::from));
+    }
+
+    // Establecer una célula con un color HSV completo
+    pub fn set_cell_with_hsv(&mut self, x: usize, y: usize, alive: bool, hsv: Option<Hsv>) {
         if x < self.width as usize && y < self.height as usize {
-            self.current_generation[x][y] = alive;
-            self.current_colors[x][y] = hue;
+            let index = self.index(x, y);
+            self.current_generation.set(x, y, alive);
+            self.current_decay[index] = 0;
+            self.born[index] = self.generation;
+            self.changed_cells.set(x, y, true);
+            match hsv {
+                Some(hsv) => self.current_colors.insert(index, CellColor::new(hsv)),
+                None => self.current_colors.remove(&index),
+            };
+            if self.activate_around(x / TILE_SIZE, y / TILE_SIZE) && self.boundary.wraps() {
+                self.activate_borders();
+            }
         }
     }
 
     // Obtener el estado de una célula específica
     pub fn get_cell(&self, x: usize, y: usize) -> bool {
         if x < self.width as usize && y < self.height as usize {
-            self.current_generation[x][y]
+            self.current_generation.get(x, y)
         } else {
             false
         }
     }
 
+    // Obtener el estado completo de una célula: 0 = muerta, 1 = viva, 2.. = muriendo
+    pub fn get_cell_state(&self, x: usize, y: usize) -> u8 {
+        if x < self.width as usize && y < self.height as usize {
+            let decay = self.current_decay[self.index(x, y)];
+            if self.current_generation.get(x, y) {
+                1
+            } else if decay > 0 {
+                decay + 1
+            } else {
+                0
+            }
+        } else {
+            0
+        }
+    }
+
     // Obtener el color de una célula específica
     pub fn get_cell_color(&self, x: usize, y: usize) -> Option<Hue> {
         if x < self.width as usize && y < self.height as usize {
-            self.current_colors[x][y]
+            self.current_colors.get(&self.index(x, y)).map(|color| color.hsv.hue)
         } else {
             None
         }
     }
 
-    // Calcular el promedio de matices (similar al Python)
-    fn average_hue(&self, hues: &[Hue]) -> Hue {
-        if hues.is_empty() {
-            return Hue::random();
-        }
-        
-        let mut x = 0.0;
-        let mut y = 0.0;
-        
-        // Convertir cada hue a coordenadas en el círculo unitario
-        for hue in hues {
-            x += (hue.value * 2.0 * PI).cos();
-            y += (hue.value * 2.0 * PI).sin();
+    // Obtener el color HSV completo de una célula específica
+    pub fn get_cell_hsv(&self, x: usize, y: usize) -> Option<Hsv> {
+        if x < self.width as usize && y < self.height as usize {
+            self.current_colors.get(&self.index(x, y)).map(|color| color.hsv)
+        } else {
+            None
         }
-        
-        x /= hues.len() as f32;
-        y /= hues.len() as f32;
-        
-        // Convertir de vuelta a hue
-        let angle = y.atan2(x);
-        Hue::new(angle / (2.0 * PI))
     }
 
-    // Contar vecinos vivos y obtener sus colores
-    fn count_neighbors_and_colors(&self, x: usize, y: usize) -> (u8, Vec<Hue>) {
+    // Célula que se lee en (x + dx, y + dy) según la condición de frontera
+    fn neighbor(&self, x: usize, y: usize, dx: i32, dy: i32) -> Option<(usize, usize)> {
+        self.boundary.resolve(
+            x as i64 + dx as i64,
+            y as i64 + dy as i64,
+            self.width as usize,
+            self.height as usize,
+        )
+    }
+
+    // Contar vecinos vivos de la vecindad 3x3
+    fn count_neighbors(&self, x: usize, y: usize) -> u32 {
         let mut count = 0;
-        let mut colors = Vec::new();
         
         for dx in -1..=1 {
             for dy in -1..=1 {
@@ -186,130 +512,1149 @@ impl GameOfLife {
                     continue; // Saltar la célula actual
                 }
                 
-                let nx = x as i32 + dx;
-                let ny = y as i32 + dy;
-                
-                // Verificar límites
-                if nx >= 0 && nx < self.width as i32 && ny >= 0 && ny < self.height as i32 {
-                    let nx = nx as usize;
-                    let ny = ny as usize;
-                    
-                    if self.current_generation[nx][ny] {
-                        count += 1;
-                        if let Some(hue) = self.current_colors[nx][ny] {
-                            colors.push(hue);
-                        }
-                    }
+                // Verificar límites según la frontera
+                if let Some((nx, ny)) = self.neighbor(x, y, dx, dy)
+                    && self.current_generation.get(nx, ny)
+                {
+                    count += 1;
                 }
             }
         }
         
-        (count, colors)
+        count
     }
 
-    // Aplicar las reglas del Juego de la Vida con colores
-    pub fn update(&mut self) {
-        for x in 0..self.width as usize {
-            for y in 0..self.height as usize {
-                let (neighbors, neighbor_colors) = self.count_neighbors_and_colors(x, y);
-                let is_alive = self.current_generation[x][y];
-                
-                // Aplicar reglas de Conway
-                let will_live = match (is_alive, neighbors) {
-                    (true, 2) | (true, 3) => true,  // Sobrevive
-                    (false, 3) => true,             // Nace
-                    _ => false,                     // Muere o permanece muerta
+    // Promedio de los colores de los vecinos vivos de la vecindad 3x3 (circular para el
+    // matiz). Solo se calcula para las células que nacen.
+    fn neighbor_color(&self, x: usize, y: usize) -> Option<Hsv> {
+        let mut sum = ColorSum::default();
+        for dx in -1..=1 {
+            for dy in -1..=1 {
+                if dx == 0 && dy == 0 {
+                    continue;
+                }
+                if let Some((nx, ny)) = self.neighbor(x, y, dx, dy)
+                    && self.current_generation.get(nx, ny)
+                    && let Some(color) = self.current_colors.get(&self.index(nx, ny))
+                {
+                    sum.add(color, 1);
+                }
+            }
+        }
+        sum.average()
+    }
+
+    // Contar vecinos con el kernel personalizado: suma de los pesos de las células vivas
+    fn count_kernel(&self, kernel: &Kernel, x: usize, y: usize) -> u32 {
+        let mut count = 0;
+        for cell in &kernel.cells {
+            if let Some((nx, ny)) = self.neighbor(x, y, cell.dx, cell.dy)
+                && self.current_generation.get(nx, ny)
+            {
+                count += cell.weight;
+            }
+        }
+        count
+    }
+
+    // Promedio de los colores del kernel, ponderado por el peso de cada célula
+    fn kernel_color(&self, kernel: &Kernel, x: usize, y: usize) -> Option<Hsv> {
+        let mut sum = ColorSum::default();
+        for cell in &kernel.cells {
+            if let Some((nx, ny)) = self.neighbor(x, y, cell.dx, cell.dy)
+                && self.current_generation.get(nx, ny)
+                && let Some(color) = self.current_colors.get(&self.index(nx, ny))
+            {
+                sum.add(color, cell.weight);
+            }
+        }
+        sum.average()
+    }
+
+    // Índice de 9 bits de la vecindad 3x3, leída por filas (NW es el bit más alto)
+    fn neighborhood_index(&self, x: usize, y: usize) -> usize {
+        let mut index = 0;
+        for dy in -1..=1 {
+            for dx in -1..=1 {
+                let alive = self
+                    .neighbor(x, y, dx, dy)
+                    .is_some_and(|(nx, ny)| self.current_generation.get(nx, ny));
+                index = (index << 1) | alive as usize;
+            }
+        }
+        index
+    }
+
+    // Número al azar de una célula en la generación actual (depende de la semilla, la
+    // generación y la posición, no del hilo)
+    fn cell_random(&self, x: usize, y: usize, stream: u64) -> u64 {
+        random::cell_random(self.seed, self.generation, x as i64, y as i64, stream)
+    }
+
+    // Color de una célula que nace a partir del color heredado de sus vecinos
+    fn born_hsv(&self, x: usize, y: usize, parent: Option<Hsv>) -> Hsv {
+        if let Some(parent) = parent {
+            // Agregar variación a cada componente, con su propia amplitud (cambia en
+            // cada generación)
+            let vary = |amount: f32, stream| {
+                if amount > 0.0 {
+                    (random::unit(self.cell_random(x, y, stream)) * 2.0 - 1.0) * amount
+                } else {
+                    0.0
+                }
+            };
+            Hsv::new(
+                Hue::new(parent.hue.value + vary(self.color_variation, random::STREAM_VARIATION)),
+                parent.saturation + vary(self.saturation_variation, random::STREAM_SATURATION),
+                parent.value + vary(self.value_variation, random::STREAM_VALUE),
+            )
+        } else {
+            Hue::new(random::unit(self.cell_random(x, y, random::STREAM_HUE))).into()
+        }
+    }
+
+    // Color de una célula que nace en (x, y), heredado de sus vecinos con la estrategia elegida
+    fn born_color(&self, x: usize, y: usize, table: Option<&SummedAreaTable>) -> CellColor {
+        let parent = if self.inheritance.is_circular_mean() {
+            // Camino rápido del promedio circular: no hace falta recorrer los padres
+            match (&self.kernel, table) {
+                (Some(kernel), _) => self.kernel_color(kernel, x, y),
+                (None, Some(table)) => table.average_color(x, y, &self.rule),
+                (None, None) => self.neighbor_color(x, y),
+            }
+        } else {
+            let seed = self.cell_random(x, y, random::STREAM_PARENT);
+            self.inheritance.inherit(&mut self.parents(x, y), seed)
+        };
+        CellColor::new(self.born_hsv(x, y, parent))
+    }
+
+    // Vecinos vivos con color de (x, y): las células del kernel o las de la vecindad de la regla
+    fn parents(&self, x: usize, y: usize) -> impl Iterator<Item = Parent> + '_ {
+        let r = self.rule.range as i32;
+        let neighborhood = self.rule.neighborhood;
+        // Red hexagonal: los vecinos de arriba y abajo son (x - 1, x) en filas pares y
+        // (x, x + 1) en impares
+        let shift = (y & 1) as i32;
+        let (kernel, square) = match &self.kernel {
+            Some(kernel) => (Some(kernel.cells.iter().map(|cell| (cell.dx, cell.dy, cell.weight))), None),
+            None => {
+                let square = (-r..=r)
+                    .flat_map(move |dy| (-r..=r).map(move |dx| (dx, dy, 1)))
+                    .filter(move |&(dx, dy, _)| {
+                        (dx, dy) != (0, 0)
+                            && match neighborhood {
+                                Neighborhood::Moore => true,
+                                Neighborhood::VonNeumann => dx.abs() + dy.abs() <= r,
+                                Neighborhood::Hexagonal => dy == 0 || dx == shift - 1 || dx == shift,
+                            }
+                    });
+                (None, Some(square))
+            }
+        };
+        kernel
+            .into_iter()
+            .flatten()
+            .chain(square.into_iter().flatten())
+            .filter_map(move |(dx, dy, weight)| {
+                let (nx, ny) = self.neighbor(x, y, dx, dy)?;
+                if !self.current_generation.get(nx, ny) {
+                    return None;
+                }
+                let index = self.index(nx, ny);
+                let color = *self.current_colors.get(&index)?;
+                Some(Parent { color, weight, age: self.generation - self.born[index] })
+            })
+    }
+
+    // Cambiar lo que representa el color de cada célula (redibuja todo el grid)
+    pub fn set_color_mode(&mut self, mode: ColorMode) {
+        self.color_mode = mode;
+        self.invalidate_render();
+    }
+
+    // Cambiar la paleta (redibuja todo el grid)
+    pub fn set_palette(&mut self, palette: Palette) {
+        self.palette = palette;
+        self.invalidate_render();
+    }
+
+    // Cambiar la forma en que las células que nacen heredan el color
+    pub fn set_color_inheritance(&mut self, inheritance: Arc<dyn ColorInheritance>) {
+        self.inheritance = inheritance;
+    }
+
+    // Calcular el siguiente estado de una célula: (viva, decaimiento, color).
+    // Solo lee la generación actual, así varias bandas de filas se pueden calcular a la vez.
+    fn step_cell(&self, x: usize, y: usize, table: Option<&SummedAreaTable>) -> (bool, u8, Option<CellColor>) {
+        let index = self.index(x, y);
+        let is_alive = self.current_generation.get(x, y);
+        let decay = self.current_decay[index];
+        // El color actual solo se busca si la célula lo conserva
+        let color = || self.current_colors.get(&index).copied();
+
+        // Célula muriendo (reglas Generations): avanza un estado hasta morir
+        if decay > 0 {
+            if decay < self.rule.dying_states() {
+                return (false, decay + 1, color());
+            }
+            return (false, 0, None);
+        }
+
+        // Aplicar la regla configurada (por defecto Conway B3/S23); las reglas
+        // no totalísticas dependen de la disposición exacta de los vecinos
+        let will_live = match (&self.kernel, table) {
+            (None, _) if self.rule.table.is_some() => {
+                self.rule.next_state_from_index(self.neighborhood_index(x, y))
+            }
+            (Some(kernel), _) => self.rule.next_state(is_alive, self.count_kernel(kernel, x, y)),
+            (None, Some(table)) => self.rule.next_state(is_alive, table.count(x, y, &self.rule, is_alive)),
+            (None, None) => self.rule.next_state(is_alive, self.count_neighbors(x, y)),
+        };
+        
+        // Manejar colores (las células muertas no tienen color)
+        if will_live {
+            if is_alive {
+                // Célula sobrevive, mantiene su color
+                return (true, 0, color());
+            }
+
+            // Nueva célula nace, hereda color de vecinos
+            (true, 0, Some(self.born_color(x, y, table)))
+        } else if is_alive && self.rule.is_generations() {
+            // Célula empieza a morir y conserva su color mientras se desvanece
+            (false, 1, color())
+        } else {
+            // Célula muere
+            (false, 0, None)
+        }
+    }
+
+    // Calcular una banda de filas a partir de `first_row`. `words` y `decay` son las filas
+    // de la banda en la siguiente generación; los colores de sus células se agregan a `colors`.
+    fn step_band(
+        &self,
+        first_row: usize,
+        words: &mut [u64],
+        decay: &mut [u8],
+        colors: &mut Vec<(usize, CellColor)>,
+        table: Option<&SummedAreaTable>,
+    ) {
+        let width = self.width as usize;
+        let words_per_row = self.current_generation.words_per_row();
+
+        words.fill(0);
+        for row in 0..decay.len() / width {
+            let y = first_row + row;
+            for k in 0..words_per_row {
+                let x0 = k * TILE_SIZE;
+                if !self.is_tile_active(k, y / TILE_SIZE) {
+                    self.copy_tile_row(row, y, k, words, decay, colors);
+                    continue;
+                }
+                for x in x0..(x0 + TILE_SIZE).min(width) {
+                    let (alive, next_decay, color) = self.step_cell(x, y, table);
+                    words[row * words_per_row + k] |= (alive as u64) << (x - x0);
+                    decay[row * width + x] = next_decay;
+                    if let Some(color) = color {
+                        colors.push((self.index(x, y), color));
+                    }
+                }
+            }
+        }
+    }
+
+    // Copiar sin cambios la parte de la fila `y` que cae en la columna de bloques `k`
+    fn copy_tile_row(
+        &self,
+        row: usize,
+        y: usize,
+        k: usize,
+        words: &mut [u64],
+        decay: &mut [u8],
+        colors: &mut Vec<(usize, CellColor)>,
+    ) {
+        let width = self.width as usize;
+        let words_per_row = self.current_generation.words_per_row();
+        let x0 = k * TILE_SIZE;
+        let x1 = (x0 + TILE_SIZE).min(width);
+        let word = self.current_generation.row(y)[k];
+
+        words[row * words_per_row + k] = word;
+        decay[row * width + x0..row * width + x1]
+            .copy_from_slice(&self.current_decay[self.index(x0, y)..self.index(x1, y)]);
+        for x in x0..x1 {
+            let index = self.index(x, y);
+            if (word >> (x - x0) & 1 == 1 || self.current_decay[index] > 0)
+                && let Some(&color) = self.current_colors.get(&index)
+            {
+                colors.push((index, color));
+            }
+        }
+    }
+
+    // Versión de step_band con los motores por palabras: calcula 64 células a la vez y
+    // solo recorre célula por célula las que nacen (para heredar el color), las que
+    // sobreviven y las que están muriendo.
+    fn step_band_bits(
+        &self,
+        first_row: usize,
+        words: &mut [u64],
+        decay: &mut [u8],
+        colors: &mut Vec<(usize, CellColor)>,
+        padded: &BitGrid,
+        step: &WordStep,
+    ) {
+        let width = self.width as usize;
+        let words_per_row = self.current_generation.words_per_row();
+        let generations = self.rule.is_generations();
+        let rows = decay.len() / width;
+
+        decay.fill(0);
+        for row in 0..rows {
+            let y = first_row + row;
+
+            // El motor Lookup calcula 4 filas de una vez: se guardan en `words` y abajo
+            // se leen como el resultado de la regla
+            if let WordStep::Blocks(table) = step
+                && row % 4 == 0
+            {
+                let block_rows = (rows - row).min(4);
+                for k in 0..words_per_row {
+                    let last_y = y + block_rows - 1;
+                    if !self.is_tile_active(k, y / TILE_SIZE) && !self.is_tile_active(k, last_y / TILE_SIZE) {
+                        continue;
+                    }
+                    let next = lookup::next_rows(padded, table, y, k);
+                    for (i, &word) in next[..block_rows].iter().enumerate() {
+                        words[(row + i) * words_per_row + k] = word;
+                    }
+                }
+            }
+
+            let current = self.current_generation.row(y);
+            for (k, &alive) in current.iter().enumerate() {
+                let x0 = k * 64;
+                let columns = (width - x0).min(64);
+                if !self.is_tile_active(k, y / TILE_SIZE) {
+                    self.copy_tile_row(row, y, k, words, decay, colors);
+                    continue;
+                }
+
+                // Las células muriendo no cuentan como vivas ni pueden nacer
+                let mut dying = 0u64;
+                if generations {
+                    for bit in 0..columns {
+                        dying |= ((self.current_decay[self.index(x0 + bit, y)] > 0) as u64) << bit;
+                    }
+                }
+
+                let (birth, survival) = match step {
+                    WordStep::Bits(rule) => bit_parallel::next_word(padded, rule, y, k),
+                    WordStep::Blocks(_) => {
+                        let next = words[row * words_per_row + k];
+                        (next, next)
+                    }
                 };
-                
-                self.next_generation[x][y] = will_live;
-                
-                // Manejar colores
-                if will_live {
-                    if is_alive {
+                let born = birth & !alive & !dying;
+                let survived = survival & alive;
+                let valid = if columns == 64 { !0 } else { (1u64 << columns) - 1 };
+                let next = (born | survived) & valid;
+                words[row * words_per_row + k] = next;
+
+                // Recorrer solo los bits activos
+                let mut pending = next;
+                while pending != 0 {
+                    let x = x0 + pending.trailing_zeros() as usize;
+                    pending &= pending - 1;
+                    let index = self.index(x, y);
+                    let color = if survived >> (x - x0) & 1 == 1 {
                         // Célula sobrevive, mantiene su color
-                        self.next_colors[x][y] = self.current_colors[x][y];
+                        self.current_colors.get(&index).copied()
                     } else {
                         // Nueva célula nace, hereda color de vecinos
-                        if !neighbor_colors.is_empty() {
-                            let mut new_hue = self.average_hue(&neighbor_colors);
-                            
-                            // Agregar variación de color
-                            if self.color_variation > 0.0 {
-                                use std::collections::hash_map::DefaultHasher;
-                                use std::hash::{Hash, Hasher};
-                                
-                                let mut hasher = DefaultHasher::new();
-                                (x, y).hash(&mut hasher);
-                                let hash = hasher.finish();
-                                let variation = (hash as f32 / u64::MAX as f32) * 2.0 - 1.0;
-                                
-                                new_hue = Hue::new(new_hue.value + variation * self.color_variation);
-                            }
-                            
-                            self.next_colors[x][y] = Some(new_hue);
-                        } else {
-                            self.next_colors[x][y] = Some(Hue::random());
+                        Some(self.born_color(x, y, None))
+                    };
+                    if let Some(color) = color {
+                        colors.push((index, color));
+                    }
+                }
+
+                // Estados de decaimiento (reglas Generations)
+                let mut fading = if generations { dying | (alive & !survived) } else { 0 };
+                while fading != 0 {
+                    let x = x0 + fading.trailing_zeros() as usize;
+                    fading &= fading - 1;
+                    let index = self.index(x, y);
+                    let next_decay = self.current_decay[index] + 1;
+                    if next_decay <= self.rule.dying_states() {
+                        decay[row * width + x] = next_decay;
+                        if let Some(&color) = self.current_colors.get(&index) {
+                            colors.push((index, color));
                         }
                     }
-                } else {
-                    // Célula muere
-                    self.next_colors[x][y] = None;
                 }
             }
         }
+    }
+
+    // Aplicar las reglas del Juego de la Vida con colores
+    pub fn update(&mut self) {
+        // Las vecindades grandes (Larger than Life) y la red hexagonal se cuentan con
+        // tablas de áreas sumadas
+        let table = (self.kernel.is_none() && !self.rule.is_totalistic_moore()).then(|| {
+            SummedAreaTable::new(
+                &self.current_generation,
+                &self.current_colors,
+                self.rule.range as usize,
+                self.boundary,
+            )
+        });
+
+        // Repartir las filas en bandas, una por hilo. Cada célula depende solo de la
+        // generación actual y de su posición, así el resultado es idéntico al de un solo hilo.
+        let width = self.width as usize;
+        let rows_per_band = (self.height as usize).div_ceil(self.threads.max(1)).max(1);
+        let mut next_generation = std::mem::replace(&mut self.next_generation, BitGrid::new(0, 0));
+        let mut next_decay = std::mem::take(&mut self.next_decay);
+        let mut next_colors = std::mem::take(&mut self.next_colors);
+        let mut band_colors = std::mem::take(&mut self.band_colors);
+        let mut padded = std::mem::replace(&mut self.padded, BitGrid::new(0, 0));
+
+        // Motores por palabras, si están elegidos y la regla lo permite. La tabla del motor
+        // Lookup solo se vuelve a construir cuando cambia la regla.
+        let bit_rule = match (self.engine, &self.kernel) {
+            (Engine::BitParallel | Engine::Lookup, None) => BitRule::new(&self.rule),
+            _ => None,
+        };
+        if let Some(rule) = &bit_rule {
+            bit_parallel::pad(&self.current_generation, self.boundary, &mut padded);
+            if self.engine == Engine::Lookup && self.block_table.as_ref().is_none_or(|table| table.rule() != rule) {
+                self.block_table = Some(BlockTable::new(rule));
+            }
+        }
+        let word_step = bit_rule.as_ref().map(|rule| match (self.engine, &self.block_table) {
+            (Engine::Lookup, Some(table)) => WordStep::Blocks(table),
+            _ => WordStep::Bits(rule),
+        });
+
+        // Los buffers de colores de cada banda se conservan entre pasos, así un paso en
+        // régimen estable no reserva memoria
+        let bands = (self.height as usize).div_ceil(rows_per_band);
+        band_colors.resize_with(bands, Vec::new);
+        for colors in &mut band_colors {
+            colors.clear();
+        }
+
+        let game = &*self;
+        let table = table.as_ref();
+        let step = |first_row: usize, words: &mut [u64], decay: &mut [u8], colors: &mut Vec<(usize, CellColor)>| {
+            match &word_step {
+                Some(step) => game.step_band_bits(first_row, words, decay, colors, &padded, step),
+                None => game.step_band(first_row, words, decay, colors, table),
+            }
+        };
+        let work = next_generation
+            .bands_mut(rows_per_band)
+            .zip(next_decay.chunks_mut((rows_per_band * width).max(1)))
+            .zip(band_colors.iter_mut())
+            .enumerate()
+            .map(|(band, ((words, decay), colors))| (band * rows_per_band, words, decay, colors));
+        if bands <= 1 {
+            // Una sola banda: se calcula en este hilo sin crear otros
+            work.for_each(|(first_row, words, decay, colors)| step(first_row, words, decay, colors));
+        } else {
+            std::thread::scope(|scope| {
+                for (first_row, words, decay, colors) in work {
+                    let step = &step;
+                    scope.spawn(move || step(first_row, words, decay, colors));
+                }
+            });
+        }
+
+        next_colors.clear();
+        for colors in &band_colors {
+            next_colors.extend(colors.iter().copied());
+        }
+        self.band_colors = band_colors;
+        self.padded = padded;
+        self.next_generation = next_generation;
+        self.next_decay = next_decay;
+        self.next_colors = next_colors;
         
         // Intercambiar generaciones
         std::mem::swap(&mut self.current_generation, &mut self.next_generation);
         std::mem::swap(&mut self.current_colors, &mut self.next_colors);
+        std::mem::swap(&mut self.current_decay, &mut self.next_decay);
+
+        self.generation += 1;
+        self.record_births();
+        self.track_changed_cells();
+        self.track_active_tiles();
+    }
+
+    // Número de bloques en cada eje
+    fn tiles(&self) -> (usize, usize) {
+        ((self.width as usize).div_ceil(TILE_SIZE), (self.height as usize).div_ceil(TILE_SIZE))
+    }
+
+    fn is_tile_active(&self, tile_x: usize, tile_y: usize) -> bool {
+        self.active_tiles[tile_y * self.tiles().0 + tile_x]
+    }
+
+    // Distancia en bloques a la que llega la vecindad actual
+    fn tile_reach(&self) -> usize {
+        let radius = match &self.kernel {
+            Some(kernel) => kernel.radius().max(0) as usize,
+            None => self.rule.range as usize,
+        };
+        radius.div_ceil(TILE_SIZE)
+    }
+
+    // Marcar como activos los bloques a los que llega la vecindad de un bloque que cambió.
+    // Devuelve true si el bloque toca el borde del grid.
+    fn activate_around(&mut self, tile_x: usize, tile_y: usize) -> bool {
+        let (tiles_x, tiles_y) = self.tiles();
+        let reach = self.tile_reach();
+        for ty in tile_y.saturating_sub(reach)..(tile_y + reach + 1).min(tiles_y) {
+            for tx in tile_x.saturating_sub(reach)..(tile_x + reach + 1).min(tiles_x) {
+                self.active_tiles[ty * tiles_x + tx] = true;
+            }
+        }
+        tile_x < reach || tile_y < reach || tile_x + reach >= tiles_x || tile_y + reach >= tiles_y
+    }
+
+    // Con fronteras que pegan bordes opuestos, un cambio en un borde llega al otro:
+    // marcar todos los bloques cercanos a los bordes
+    fn activate_borders(&mut self) {
+        let (tiles_x, tiles_y) = self.tiles();
+        let reach = self.tile_reach();
+        for ty in 0..tiles_y {
+            for tx in 0..tiles_x {
+                if tx < reach || ty < reach || tx + reach >= tiles_x || ty + reach >= tiles_y {
+                    self.active_tiles[ty * tiles_x + tx] = true;
+                }
+            }
+        }
+    }
+
+    // Comparar la generación nueva con la anterior y activar para el siguiente paso solo
+    // los bloques cuya vecindad cambió: los demás darían exactamente el mismo resultado
+    fn track_active_tiles(&mut self) {
+        let (tiles_x, tiles_y) = self.tiles();
+        let width = self.width as usize;
+        let height = self.height as usize;
+        let generations = self.rule.is_generations();
+
+        let mut changed = Vec::new();
+        for ty in 0..tiles_y {
+            for tx in 0..tiles_x {
+                let rows = ty * TILE_SIZE..((ty + 1) * TILE_SIZE).min(height);
+                let x0 = tx * TILE_SIZE;
+                let x1 = (x0 + TILE_SIZE).min(width);
+                let differs = rows.clone().any(|y| {
+                    self.current_generation.row(y)[tx] != self.next_generation.row(y)[tx]
+                        || (generations
+                            && self.current_decay[self.index(x0, y)..self.index(x1, y)]
+                                != self.next_decay[self.index(x0, y)..self.index(x1, y)])
+                });
+                if differs {
+                    changed.push((tx, ty));
+                }
+            }
+        }
+
+        self.active_tiles.fill(false);
+        let mut border = false;
+        for (tx, ty) in changed {
+            border |= self.activate_around(tx, ty);
+        }
+        if border && self.boundary.wraps() {
+            self.activate_borders();
+        }
+    }
+
+    // Guardar la generación de las células que acaban de nacer (vivas ahora y no en el paso
+    // anterior); las que sobreviven o están muriendo conservan la suya
+    fn record_births(&mut self) {
+        let width = self.width as usize;
+        for y in 0..self.height as usize {
+            let current = self.current_generation.row(y);
+            let previous = self.next_generation.row(y);
+            for (k, (&now, &before)) in current.iter().zip(previous).enumerate() {
+                let mut births = now & !before;
+                while births != 0 {
+                    let x = k * 64 + births.trailing_zeros() as usize;
+                    births &= births - 1;
+                    self.born[y * width + x] = self.generation;
+                }
+            }
+        }
+    }
+
+    // Edad de una célula viva o muriendo: generaciones desde que nació (None si está muerta)
+    pub fn get_cell_age(&self, x: usize, y: usize) -> Option<u64> {
+        (self.get_cell_state(x, y) > 0).then(|| self.generation - self.born[self.index(x, y)])
+    }
+
+    // Marcar las células cuyo estado cambió en el último paso. Las que sobreviven conservan
+    // su color, así que basta comparar el grid de bits y los estados de decaimiento.
+    fn track_changed_cells(&mut self) {
+        let width = self.width as usize;
+        // En los modos por edad el color de todas las células vivas cambia en cada paso
+        let by_age = matches!(self.color_mode, ColorMode::Age | ColorMode::AgeShaded);
+        for y in 0..self.height as usize {
+            let current = self.current_generation.row(y);
+            let previous = self.next_generation.row(y);
+            for (k, changed) in self.changed_cells.row_mut(y).iter_mut().enumerate() {
+                *changed |= current[k] ^ previous[k];
+                if by_age {
+                    *changed |= current[k];
+                }
+            }
+        }
+        if self.rule.is_generations() {
+            for (index, (current, previous)) in self.current_decay.iter().zip(&self.next_decay).enumerate() {
+                if current != previous {
+                    self.changed_cells.set(index % width, index / width, true);
+                }
+            }
+        }
+    }
+
+    // Hacer que el próximo render_changes redibuje todo el grid (después de limpiar el
+    // framebuffer o de mover la cámara)
+    pub fn invalidate_render(&mut self) {
+        self.repaint_all = true;
+    }
+
+    // Recalcular todos los bloques en el siguiente paso (después de cambiar la regla, etc.)
+    fn activate_all(&mut self) {
+        self.active_tiles.fill(true);
+    }
+
+    // Color de una célula que está muriendo: versión atenuada de su color heredado o,
+    // si no tiene, del color por defecto
+    fn fading_color(&self, color: Option<&CellColor>, decay: u8) -> Color {
+        ColorMode::Inherited.cell_color(&self.palette, color, self.fade(decay), || 0.0)
+    }
+
+    // Brillo de una célula según su estado de decaimiento (1.0 = viva)
+    fn fade(&self, decay: u8) -> f32 {
+        1.0 - decay as f32 / (self.rule.states - 1) as f32
+    }
+
+    // Posición de la edad de una célula en el degradado de los modos por edad
+    fn age_fraction(&self, index: usize) -> f32 {
+        age_fraction(self.generation - self.born[index], self.age_scale)
+    }
+
+    // Color con el que se dibuja una célula según su estado
+    fn cell_color(&self, x: usize, y: usize) -> Color {
+        let index = self.index(x, y);
+        let decay = self.current_decay[index];
+        let alive = self.current_generation.get(x, y);
+        if !alive && decay == 0 {
+            return self.palette.background;
+        }
+        let color = self.current_colors.get(&index);
+        self.color_mode.cell_color(&self.palette, color, self.fade(decay), || self.age_fraction(index))
+    }
+
+    // Color de todas las células, por filas. Con los colores heredados primero se pintan
+    // las células vivas y muriendo con los colores por defecto y después se recorre la
+    // capa de colores una sola vez, sin buscar célula por célula.
+    fn cell_colors(&self) -> Vec<Color> {
+        let width = self.width as usize;
+        let mut colors = vec![self.palette.background; width * self.height as usize];
+
+        // Los demás modos calculan el color de cada célula viva o muriendo
+        if self.color_mode != ColorMode::Inherited {
+            for (index, color) in colors.iter_mut().enumerate() {
+                let (x, y) = (index % width, index / width);
+                if self.current_generation.get(x, y) || self.current_decay[index] > 0 {
+                    *color = self.cell_color(x, y);
+                }
+            }
+            return colors;
+        }
+
+        for y in 0..self.height as usize {
+            for (k, &word) in self.current_generation.row(y).iter().enumerate() {
+                let mut alive = word;
+                while alive != 0 {
+                    let x = k * 64 + alive.trailing_zeros() as usize;
+                    alive &= alive - 1;
+                    colors[y * width + x] = self.palette.alive;
+                }
+            }
+        }
+        if self.rule.is_generations() {
+            for (index, &decay) in self.current_decay.iter().enumerate() {
+                if decay > 0 {
+                    colors[index] = self.fading_color(None, decay);
+                }
+            }
+        }
+
+        for (&index, color) in &self.current_colors {
+            colors[index] = match self.current_decay[index] {
+                0 => self.palette.sample_hsv(color.hsv),
+                decay => self.fading_color(Some(color), decay),
+            };
+        }
+        colors
     }
 
     // Renderizar el estado actual en el framebuffer con colores
     pub fn render(&self, framebuffer: &mut Framebuffer, offset_x: u32, offset_y: u32, scale: u32) {
-        for x in 0..self.width as usize {
+        if self.rule.is_hexagonal() {
+            self.render_hexagonal(framebuffer, offset_x, offset_y, scale);
+            return;
+        }
+
+        // Cada célula es un cuadrado de tamaño 'scale'
+        let colors = self.cell_colors();
+        framebuffer.blit_scaled(offset_x, offset_y, self.width, self.height, &colors, scale);
+    }
+
+    // Redibujar solo las células que cambiaron desde el último clear_changes (todo el grid
+    // si se limpió o se llamó a invalidate_render). El resto del framebuffer debe conservar
+    // lo dibujado antes con la misma posición y escala.
+    pub fn render_changes(&self, framebuffer: &mut Framebuffer, offset_x: u32, offset_y: u32, scale: u32) {
+        if self.repaint_all {
+            self.render(framebuffer, offset_x, offset_y, scale);
+        } else {
+            let spans = if self.rule.is_hexagonal() { hex_spans(scale) } else { Vec::new() };
             for y in 0..self.height as usize {
-                let color = if self.current_generation[x][y] {
-                    // Célula viva: usar color del hue o color por defecto
-                    if let Some(hue) = self.current_colors[x][y] {
-                        hue.to_color()
-                    } else {
-                        self.alive_color
-                    }
-                } else {
-                    self.dead_color
-                };
-                
-                framebuffer.set_current_color(color);
-                
-                // Dibujar cada célula como un cuadrado de tamaño 'scale'
-                for sx in 0..scale {
-                    for sy in 0..scale {
-                        let pixel_x = offset_x + (x as u32 * scale) + sx;
-                        let pixel_y = offset_y + (y as u32 * scale) + sy;
-                        framebuffer.set_pixel(pixel_x, pixel_y);
+                for (k, &word) in self.changed_cells.row(y).iter().enumerate() {
+                    let mut changed = word;
+                    while changed != 0 {
+                        let x = k * 64 + changed.trailing_zeros() as usize;
+                        changed &= changed - 1;
+                        let color = self.cell_color(x, y);
+                        if self.rule.is_hexagonal() {
+                            self.repaint_hexagon(framebuffer, (x, y), (offset_x, offset_y), scale, &spans, color);
+                        } else {
+                            let (left, top) = (offset_x + x as u32 * scale, offset_y + y as u32 * scale);
+                            framebuffer.fill_rect(left, top, scale, scale, color);
+                        }
                     }
                 }
             }
         }
     }
 
-    // Obtener estadísticas del juego
-    pub fn get_stats(&self) -> (u32, u32) {
-        let mut alive_count = 0;
-        let total_cells = self.width * self.height;
-        
-        for x in 0..self.width as usize {
-            for y in 0..self.height as usize {
-                if self.current_generation[x][y] {
-                    alive_count += 1;
+    // Olvidar los cambios ya dibujados (después de mostrar el frame de render_changes)
+    pub fn clear_changes(&mut self) {
+        self.changed_cells.clear();
+        self.repaint_all = false;
+    }
+
+    // Esquina superior izquierda del cuadrado donde se dibuja el hexágono de una célula:
+    // las filas impares van desplazadas media célula y las filas están separadas 3/4 de
+    // la altura para que las puntas encajen
+    fn hexagon_origin(&self, x: usize, y: usize, (offset_x, offset_y): (u32, u32), scale: u32) -> (u32, u32) {
+        let row_pitch = (scale * 3 / 4).max(1);
+        (offset_x + x as u32 * scale + (y as u32 % 2) * scale / 2, offset_y + y as u32 * row_pitch)
+    }
+
+    // Dibujar la red hexagonal: hexágonos con punta arriba, fila por fila
+    fn render_hexagonal(&self, framebuffer: &mut Framebuffer, offset_x: u32, offset_y: u32, scale: u32) {
+        let spans = hex_spans(scale);
+        let colors = self.cell_colors();
+        for y in 0..self.height as usize {
+            for x in 0..self.width as usize {
+                let color = colors[self.index(x, y)];
+                let (left, top) = self.hexagon_origin(x, y, (offset_x, offset_y), scale);
+                for (sy, &(start, width)) in spans.iter().enumerate() {
+                    framebuffer.fill_rect(left + start, top + sy as u32, width, 1, color);
+                }
+            }
+        }
+    }
+
+    // Redibujar el hexágono de una célula sin tapar los de la fila siguiente: al dibujar
+    // todo el grid, las puntas de esa fila quedan encima, así que esos píxeles se saltan
+    fn repaint_hexagon(
+        &self,
+        framebuffer: &mut Framebuffer,
+        (x, y): (usize, usize),
+        offset: (u32, u32),
+        scale: u32,
+        spans: &[(u32, u32)],
+        color: Color,
+    ) {
+        let (left, top) = self.hexagon_origin(x, y, offset, scale);
+        let below = (y + 1 < self.height as usize).then(|| x.saturating_sub(1)..=(x + 1).min(self.width as usize - 1));
+        let covered = |px: u32, py: u32| {
+            below.clone().into_iter().flatten().any(|bx| {
+                let (bx, by) = self.hexagon_origin(bx, y + 1, offset, scale);
+                (by..by + scale).contains(&py) && {
+                    let (start, width) = spans[(py - by) as usize];
+                    (bx + start..bx + start + width).contains(&px)
+                }
+            })
+        };
+
+        for (sy, &(start, width)) in spans.iter().enumerate() {
+            let py = top + sy as u32;
+            // Tramos consecutivos de píxeles que no tapa la fila siguiente
+            let mut run: Option<u32> = None;
+            for px in left + start..=left + start + width {
+                let visible = px < left + start + width && !covered(px, py);
+                match (run, visible) {
+                    (None, true) => run = Some(px),
+                    (Some(first), false) => {
+                        framebuffer.fill_rect(first, py, px - first, 1, color);
+                        run = None;
+                    }
+                    _ => {}
                 }
             }
         }
+    }
+
+    // Tamaño en píxeles que ocupa el grid al dibujarlo con la escala dada
+    pub fn render_size(&self, scale: u32) -> (u32, u32) {
+        if self.rule.is_hexagonal() {
+            let row_pitch = (scale * 3 / 4).max(1);
+            (self.width * scale + scale / 2, self.height.saturating_sub(1) * row_pitch + scale)
+        } else {
+            (self.width * scale, self.height * scale)
+        }
+    }
+
+    // Obtener estadísticas del juego: (células vivas, células totales, bloques activos)
+    pub fn get_stats(&self) -> (u32, u32, u32) {
+        let alive_count = self.current_generation.count_ones();
+        let total_cells = self.width * self.height;
+        let active_tiles = self.active_tiles.iter().filter(|&&active| active).count() as u32;
         
-        (alive_count, total_cells)
+        (alive_count, total_cells, active_tiles)
+    }
+
+    // Número total de bloques de TILE_SIZE x TILE_SIZE
+    pub fn tile_count(&self) -> u32 {
+        let (tiles_x, tiles_y) = self.tiles();
+        (tiles_x * tiles_y) as u32
     }
 
     // Configurar variación de color
     pub fn set_color_variation(&mut self, variation: f32) {
-        self.color_variation = variation.max(0.0).min(1.0);
+        self.color_variation = variation.clamp(0.0, 1.0);
+    }
+
+    // Configurar la variación de la saturación y del brillo de las células que nacen
+    pub fn set_saturation_variation(&mut self, variation: f32) {
+        self.saturation_variation = variation.clamp(0.0, 1.0);
+    }
+
+    pub fn set_value_variation(&mut self, variation: f32) {
+        self.value_variation = variation.clamp(0.0, 1.0);
+    }
+
+    // Cambiar la regla en tiempo de ejecución
+    pub fn set_rule(&mut self, rule: Rule) {
+        // Las células en estados que la nueva regla no tiene mueren directamente
+        for index in 0..self.current_decay.len() {
+            if self.current_decay[index] > rule.dying_states() {
+                self.current_decay[index] = 0;
+                self.current_colors.remove(&index);
+            }
+        }
+        self.rule = rule;
+        self.activate_all();
+        self.invalidate_render();
+    }
+
+    // Usar una vecindad personalizada (None vuelve a la vecindad de la regla)
+    pub fn set_kernel(&mut self, kernel: Option<Kernel>) {
+        self.kernel = kernel;
+        self.activate_all();
+    }
+
+    // Cambiar la condición de frontera (bordes muertos, toroide, Klein, cruce o espejo)
+    pub fn set_boundary(&mut self, boundary: Boundary) {
+        self.boundary = boundary;
+        self.activate_all();
+    }
+
+    // Número de hilos con los que se calcula cada generación
+    pub fn set_threads(&mut self, threads: usize) {
+        self.threads = threads.max(1);
+    }
+
+    // Elegir el motor con el que se calcula cada generación
+    pub fn set_engine(&mut self, engine: Engine) {
+        self.engine = engine;
+    }
+
+    // Cambiar la regla a partir de una cadena como "B36/S23" o "23/36"
+    pub fn set_rule_str(&mut self, rule: &str) -> Result<(), RuleParseError> {
+        self.set_rule(rule.parse()?);
+        Ok(())
+    }
+}
+
+// Tramo horizontal (inicio, ancho) de cada fila de píxeles de un hexágono con punta
+// arriba de lado `scale`. Según la distancia vertical al centro: en el cuarto superior
+// e inferior el hexágono se estrecha hacia la punta
+fn hex_spans(scale: u32) -> Vec<(u32, u32)> {
+    let size = scale as f32;
+    (0..scale)
+        .map(|sy| {
+            let dy = (sy as f32 + 0.5 - size / 2.0).abs();
+            let half_width = if dy <= size / 4.0 {
+                size / 2.0
+            } else {
+                (size / 2.0 - dy) * 2.0
+            };
+            let inside: Vec<u32> = (0..scale)
+                .filter(|&sx| (sx as f32 + 0.5 - size / 2.0).abs() <= half_width)
+                .collect();
+            match (inside.first(), inside.last()) {
+                (Some(&first), Some(&last)) => (first, last - first + 1),
+                _ => (0, 0),
+            }
+        })
+        .collect()
+}
+
+// Multiplicar el brillo de un color RGB por `factor`
+fn scale_color(color: Color, factor: f32) -> Color {
+    let scale = |channel: u8| (channel as f32 * factor) as u8;
+    Color::new(scale(color.r), scale(color.g), scale(color.b), 255)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::random::Rng;
+
+    // Grid con una población pseudoaleatoria fija: un tercio de las células vivas, con colores
+    fn seeded(width: u32, height: u32, rule: &str) -> GameOfLife {
+        let mut game = GameOfLife::with_seed(width, height, 7);
+        game.set_rule_str(rule).unwrap();
+        game.set_color_variation(0.2);
+        let mut rng = Rng::new(5);
+        for y in 0..height as usize {
+            for x in 0..width as usize {
+                if rng.next_u64().is_multiple_of(3) {
+                    let hue = Hue::new(random::unit(rng.next_u64()));
+                    game.set_cell_with_color(x, y, true, Some(hue));
+                }
+            }
+        }
+        game
+    }
+
+    // Estado y color exacto (bits del matiz, la saturación y el brillo) de cada célula
+    fn cells(game: &GameOfLife) -> Vec<(u8, Option<[u32; 3]>)> {
+        let mut cells = Vec::new();
+        for y in 0..game.height as usize {
+            for x in 0..game.width as usize {
+                let hsv = game.get_cell_hsv(x, y);
+                let bits = hsv.map(|hsv| [hsv.hue.value, hsv.saturation, hsv.value].map(f32::to_bits));
+                cells.push((game.get_cell_state(x, y), bits));
+            }
+        }
+        cells
+    }
+
+    // Avanzar `game` comparando cada generación con la referencia: motor célula por
+    // célula, un solo hilo y todos los bloques recalculados en cada paso
+    fn assert_matches_reference(game: GameOfLife, steps: usize, label: &str) {
+        let reference = seeded(game.width, game.height, &game.rule.to_string());
+        assert_generations_match(game, reference, steps, label);
+    }
+
+    // Igual que assert_matches_reference, con la población inicial de `reference`. Devuelve
+    // los bloques que `game` se saltó en total.
+    fn assert_generations_match(mut game: GameOfLife, mut reference: GameOfLife, steps: usize, label: &str) -> u32 {
+        reference.set_kernel(game.kernel.clone());
+        reference.set_boundary(game.boundary);
+        reference.set_threads(1);
+        reference.set_engine(Engine::PerCell);
+        assert_eq!(cells(&game), cells(&reference), "{label}: población inicial");
+        let mut skipped = 0;
+        for step in 1..=steps {
+            reference.activate_all();
+            reference.update();
+            game.update();
+            skipped += game.tile_count() - game.get_stats().2;
+            assert!(cells(&game) == cells(&reference), "{label}: generación {step}");
+        }
+        skipped
+    }
+
+    #[test]
+    fn clone_continues_identically() {
+        let mut game = seeded(131, 77, "B3/S2-i34q");
+        game.set_engine(Engine::Lookup);
+        for _ in 0..3 {
+            game.update();
+        }
+        let mut copy = game.clone();
+        let mut reused = seeded(131, 77, "B36/S23");
+        reused.clone_from(&game);
+        assert_eq!(copy.generation, game.generation);
+        for step in 1..=10 {
+            game.update();
+            copy.update();
+            reused.update();
+            assert!(cells(&copy) == cells(&game), "generación {step}");
+            assert!(cells(&reused) == cells(&game), "generación {step}");
+        }
+    }
+
+    #[test]
+    fn threads_match_reference() {
+        for rule in ["B3/S23", "B3/S2-i34q", "B2/S34H", "B2/S/C4", "R3,C0,M0,S2..12,B5..9,NM"] {
+            for threads in [2, 3, 7] {
+                let mut game = seeded(131, 77, rule);
+                game.set_threads(threads);
+                assert_matches_reference(game, 10, &format!("{rule} con {threads} hilos"));
+            }
+        }
+    }
+
+    #[test]
+    fn bit_parallel_matches_reference() {
+        for (width, height) in [(131, 77), (64, 10), (63, 5), (65, 3), (1, 1)] {
+            for rule in ["B3/S23", "B36/S23", "B1357/S1357", "B0/S8", "B2/S/C4", "B3/S012345678/C5"] {
+                for boundary in Boundary::ALL {
+                    let mut game = seeded(width, height, rule);
+                    game.set_boundary(boundary);
+                    game.set_engine(Engine::BitParallel);
+                    game.set_threads(3);
+                    assert_matches_reference(game, 12, &format!("{rule} {boundary:?} {width}x{height}"));
+                }
+            }
+        }
+    }
+
+    // Grupos de células separados, algunos junto a los bordes, con el resto del grid vacío
+    fn clusters(rule: &str, kernel: Option<Kernel>) -> GameOfLife {
+        let (width, height) = (192, 192);
+        let mut game = GameOfLife::with_seed(width as u32, height as u32, 7);
+        game.set_rule_str(rule).unwrap();
+        game.set_kernel(kernel);
+        let mut rng = Rng::new(3);
+        for (cx, cy) in [(20, 20), (180, 180), (5, 100)] {
+            for y in cy - 5..(cy + 8).min(height) {
+                for x in cx - 5..(cx + 8).min(width) {
+                    if rng.next_u64().is_multiple_of(3) {
+                        let hue = Hue::new(random::unit(rng.next_u64()));
+                        game.set_cell_with_color(x, y, true, Some(hue));
+                    }
+                }
+            }
+        }
+        game
+    }
+
+    #[test]
+    fn skipped_tiles_match_reference() {
+        let rules = [
+            ("B3/S23", None),
+            ("B3/S2-i34q", None),
+            ("B2/S34H", None),
+            ("B2/S/C4", None),
+            ("R2,C0,M1,S6..12,B7..9,NM", None),
+            ("B3/S23", Some(Kernel::knight())),
+        ];
+        for (rule, kernel) in rules {
+            for boundary in Boundary::ALL {
+                let mut game = clusters(rule, kernel.clone());
+                game.set_boundary(boundary);
+                let label = format!("{rule} {boundary:?} kernel {}", kernel.is_some());
+                let skipped = assert_generations_match(game, clusters(rule, kernel.clone()), 20, &label);
+                if boundary == Boundary::Dead {
+                    assert!(skipped > 0, "{label}: no se saltó ningún bloque");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn lookup_matches_reference() {
+        for (width, height) in [(131, 77), (64, 10), (63, 5), (65, 3), (1, 1)] {
+            for rule in ["B3/S23", "B36/S23", "B1357/S1357", "B0/S8", "B2/S/C4", "B3/S012345678/C5"] {
+                for boundary in Boundary::ALL {
+                    let mut game = seeded(width, height, rule);
+                    game.set_boundary(boundary);
+                    game.set_engine(Engine::Lookup);
+                    game.set_threads(3);
+                    assert_matches_reference(game, 12, &format!("{rule} {boundary:?} {width}x{height}"));
+                }
+            }
+        }
+        // Con un kernel o una regla que no es de la vecindad 3x3 se usa el motor célula por célula
+        let mut game = clusters("B2/S34H", None);
+        game.set_engine(Engine::Lookup);
+        assert_generations_match(game, clusters("B2/S34H", None), 10, "Lookup con B2/S34H");
+    }
+
+    // Grid de 7x7 con algunas células vivas de colores alrededor de (3, 3)
+    fn kernel_neighborhood() -> GameOfLife {
+        let mut game = GameOfLife::with_seed(7, 7, 7);
+        for (x, y, hue) in [(3, 2, 0.0), (4, 3, 0.25), (2, 2, 0.5), (1, 2, 0.75), (5, 4, 0.1), (3, 5, 0.6)] {
+            game.set_cell_with_color(x, y, true, Some(Hue::new(hue)));
+        }
+        game
+    }
+
+    #[test]
+    fn weighted_kernel_sums() {
+        let game = kernel_neighborhood();
+        // Bordes con peso 2 y esquinas con peso 1: (3, 3) ve N y E (2 + 2) y NO (1), y
+        // (2, 3) ve N (2) y las esquinas NO y NE (1 + 1)
+        let weighted = Kernel::from_grid("121\n2.2\n121").unwrap();
+        assert_eq!(game.count_kernel(&weighted, 3, 3), 5);
+        assert_eq!(game.count_kernel(&weighted, 2, 3), 4);
+        assert_eq!(game.count_kernel(&weighted, 0, 0), 0);
+        // Salto de caballo: (1, 2) está a (-2, -1) y (5, 4) a (2, 1)
+        assert_eq!(game.count_kernel(&Kernel::knight(), 3, 3), 2);
+        // Cruz de radio 2: (3, 2), (4, 3) y (3, 5)
+        assert_eq!(game.count_kernel(&Kernel::cross(2), 3, 3), 3);
+        // Desde (5, 5) cuentan (5, 4) y (3, 5); (7, 5) y (5, 7) quedan fuera del grid
+        assert_eq!(game.count_kernel(&Kernel::cross(2), 5, 5), 2);
+
+        // La regla ve la suma ponderada: con B5 nacen (3, 3) y (4, 4), que ve N y E (2 + 2)
+        // y SO (1)
+        let mut game = kernel_neighborhood();
+        game.set_rule_str("B5/S").unwrap();
+        game.set_kernel(Some(weighted));
+        game.update();
+        let alive: Vec<_> = (0..7)
+            .flat_map(|y| (0..7).map(move |x| (x, y)))
+            .filter(|&(x, y)| game.get_cell(x, y))
+            .collect();
+        assert_eq!(alive, [(3, 3), (4, 4)]);
+    }
+
+    #[test]
+    fn weighted_kernel_colors() {
+        let game = kernel_neighborhood();
+        let weighted = Kernel::from_grid("121\n2.2\n121").unwrap();
+        // Promedio circular: 2 * (1, 0) por el matiz 0.0, 2 * (0, 1) por 0.25 y 1 * (-1, 0)
+        // por 0.5, que suman (1, 2)
+        let hsv = game.kernel_color(&weighted, 3, 3).unwrap();
+        assert!((hsv.hue.value - 2f32.atan2(1.0) / (2.0 * PI)).abs() < 1e-5, "{}", hsv.hue.value);
+        assert_eq!((hsv.saturation, hsv.value), (1.0, 1.0));
+        // Sin pesos los tres vectores suman (0, 1)
+        let hsv = game.kernel_color(&Kernel::moore(1), 3, 3).unwrap();
+        assert!((hsv.hue.value - 0.25).abs() < 1e-5, "{}", hsv.hue.value);
+
+        // Las demás estrategias reciben el peso de cada padre
+        let mut game = kernel_neighborhood();
+        game.set_kernel(Some(weighted));
+        let mut parents: Vec<_> = game
+            .parents(3, 3)
+            .map(|parent| (parent.color.hsv.hue.value, parent.weight))
+            .collect();
+        parents.sort_by(|a, b| a.0.total_cmp(&b.0));
+        assert_eq!(parents, [(0.0, 2), (0.25, 2), (0.5, 1)]);
     }
 }
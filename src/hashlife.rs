@@ -0,0 +1,508 @@
+use crate::boundary::Boundary;
+use crate::game_of_life::GameOfLife;
+use crate::hensel::CENTER;
+use crate::rule::Rule;
+use std::collections::HashMap;
+
+// HashLife: el plano infinito se guarda como un árbol cuaternario donde los nodos iguales
+// se comparten, y el resultado de avanzar cada nodo se memoriza. Así los patrones
+// repetitivos (cañones, methuselahs ya estabilizados) avanzan 2^k generaciones de golpe.
+// Solo guarda si cada célula está viva: los colores se pierden en este modo. Un grid con
+// bordes muertos se copia rodeado por un marco de células pared, que nunca cambian y se
+// cuentan como muertas: así los saltos siguen siendo exactos aunque el patrón toque el borde.
+
+type NodeId = u32;
+
+// Nodos de nivel 0: una sola célula
+const DEAD: NodeId = 0;
+const ALIVE: NodeId = 1;
+const WALL: NodeId = 2; // Fuera del grid: nunca nace ni cuenta como vecina viva
+
+// Mayor salto permitido (2^56 generaciones) para que las coordenadas quepan en i64
+pub const MAX_STEP_LOG: u8 = 56;
+
+// Pasados estos nodos se descartan los que ya no forman parte del patrón
+const COLLECT_THRESHOLD: usize = 1 << 22;
+
+#[derive(Clone, Copy)]
+struct Node {
+    children: [NodeId; 4], // nw, ne, sw, se
+    level: u8,             // El nodo cubre 2^level x 2^level células
+    population: u64,
+}
+
+pub struct HashLife {
+    nodes: Vec<Node>,
+    index: HashMap<[NodeId; 4], NodeId>,
+    results: HashMap<(NodeId, u8), NodeId>, // (nodo, log2 del salto) -> centro avanzado
+    empty: Vec<NodeId>,                     // Nodo vacío de cada nivel
+    table: Box<[bool; 512]>,
+    root: NodeId, // Cubre [-2^(nivel-1), 2^(nivel-1)) en ambos ejes
+    pub generation: u64,
+}
+
+impl HashLife {
+    // None si la regla no se puede simular: HashLife necesita la vecindad 3x3 con dos
+    // estados, y las reglas con B0 harían nacer células en todo el plano infinito
+    pub fn new(rule: &Rule) -> Option<Self> {
+        let table = rule.transition_table()?;
+        if rule.is_generations() || table[0] {
+            return None;
+        }
+
+        let cell = |population| Node {
+            children: [DEAD; 4],
+            level: 0,
+            population,
+        };
+        let mut life = HashLife {
+            nodes: vec![cell(0), cell(1), cell(0)],
+            index: HashMap::new(),
+            results: HashMap::new(),
+            empty: vec![DEAD],
+            table,
+            root: DEAD,
+            generation: 0,
+        };
+        life.root = life.empty(3);
+        Some(life)
+    }
+
+    // Copiar las células vivas de un grid (la célula (x, y) del grid queda en (x, y)),
+    // rodeadas por un marco de paredes de una célula de ancho. La cuenta de generaciones
+    // sigue desde la del grid. None si la regla, el kernel o la frontera del grid no se
+    // pueden simular con HashLife (las fronteras que pegan bordes no tienen un plano
+    // infinito equivalente).
+    pub fn from_game(game: &GameOfLife) -> Option<Self> {
+        if game.kernel.is_some() || game.boundary != Boundary::Dead {
+            return None;
+        }
+        let mut life = HashLife::new(&game.rule)?;
+        life.generation = game.generation;
+        let size = game.width.max(game.height) as u64 + 1;
+        let level = (size.next_power_of_two().trailing_zeros() as u8 + 1).max(3);
+        let half = 1i64 << (level - 1);
+        life.root = life.build(game, level, -half, -half);
+        Some(life)
+    }
+
+    // Construir el nodo del nivel dado cuya esquina superior izquierda es (x0, y0); el
+    // marco de paredes ocupa las filas -1 y height y las columnas -1 y width
+    fn build(&mut self, game: &GameOfLife, level: u8, x0: i64, y0: i64) -> NodeId {
+        let size = 1i64 << level;
+        let (width, height) = (game.width as i64, game.height as i64);
+        if x0 + size <= -1 || y0 + size <= -1 || x0 > width || y0 > height {
+            return self.empty(level);
+        }
+        if level == 0 {
+            return if !(0..width).contains(&x0) || !(0..height).contains(&y0) {
+                WALL
+            } else if game.get_cell(x0 as usize, y0 as usize) {
+                ALIVE
+            } else {
+                DEAD
+            };
+        }
+        let half = size / 2;
+        let nw = self.build(game, level - 1, x0, y0);
+        let ne = self.build(game, level - 1, x0 + half, y0);
+        let sw = self.build(game, level - 1, x0, y0 + half);
+        let se = self.build(game, level - 1, x0 + half, y0 + half);
+        self.join(nw, ne, sw, se)
+    }
+
+    // Escribir las células en el grid (sin colores) y avanzar su cuenta de generaciones; las
+    // células escritas empiezan con edad 0
+    pub fn write_to_game(&self, game: &mut GameOfLife) {
+        game.clear_grid();
+        game.generation = self.generation;
+        let (width, height) = (game.width as i64, game.height as i64);
+        self.for_each_alive(self.root, self.top_left(), &mut |x, y| {
+            if (0..width).contains(&x) && (0..height).contains(&y) {
+                game.set_cell_with_color(x as usize, y as usize, true, None);
+            }
+        });
+    }
+
+    // Recorrer las células vivas de un nodo con esquina superior izquierda (x0, y0)
+    fn for_each_alive(&self, node: NodeId, (x0, y0): (i64, i64), visit: &mut impl FnMut(i64, i64)) {
+        let current = self.nodes[node as usize];
+        if current.population == 0 {
+            return;
+        }
+        if current.level == 0 {
+            visit(x0, y0);
+            return;
+        }
+        let half = 1i64 << (current.level - 1);
+        let [nw, ne, sw, se] = current.children;
+        self.for_each_alive(nw, (x0, y0), visit);
+        self.for_each_alive(ne, (x0 + half, y0), visit);
+        self.for_each_alive(sw, (x0, y0 + half), visit);
+        self.for_each_alive(se, (x0 + half, y0 + half), visit);
+    }
+
+    fn level(&self) -> u8 {
+        self.nodes[self.root as usize].level
+    }
+
+    fn top_left(&self) -> (i64, i64) {
+        let half = -(1i64 << (self.level() - 1));
+        (half, half)
+    }
+
+    // Nodo con esos cuatro hijos, reutilizando el existente si ya se creó
+    fn join(&mut self, nw: NodeId, ne: NodeId, sw: NodeId, se: NodeId) -> NodeId {
+        let children = [nw, ne, sw, se];
+        if let Some(&id) = self.index.get(&children) {
+            return id;
+        }
+        let id = self.nodes.len() as NodeId;
+        self.nodes.push(Node {
+            children,
+            level: self.nodes[nw as usize].level + 1,
+            population: children
+                .iter()
+                .map(|&child| self.nodes[child as usize].population)
+                .sum(),
+        });
+        self.index.insert(children, id);
+        id
+    }
+
+    fn empty(&mut self, level: u8) -> NodeId {
+        while self.empty.len() <= level as usize {
+            let below = *self.empty.last().unwrap_or(&DEAD);
+            let node = self.join(below, below, below, below);
+            self.empty.push(node);
+        }
+        self.empty[level as usize]
+    }
+
+    fn children(&self, node: NodeId) -> [NodeId; 4] {
+        self.nodes[node as usize].children
+    }
+
+    // Establecer una célula
+    pub fn set_cell(&mut self, x: i64, y: i64, alive: bool) {
+        // Agrandar el árbol hasta que la célula quede dentro
+        loop {
+            let half = 1i64 << (self.level() - 1);
+            if (-half..half).contains(&x) && (-half..half).contains(&y) {
+                break;
+            }
+            self.root = self.expand(self.root);
+        }
+        let (x0, y0) = self.top_left();
+        self.root = self.set_in(self.root, x - x0, y - y0, alive);
+    }
+
+    fn set_in(&mut self, node: NodeId, x: i64, y: i64, alive: bool) -> NodeId {
+        let level = self.nodes[node as usize].level;
+        if level == 0 {
+            return if alive { ALIVE } else { DEAD };
+        }
+        let half = 1i64 << (level - 1);
+        let mut children = self.children(node);
+        let quadrant = (y >= half) as usize * 2 + (x >= half) as usize;
+        children[quadrant] = self.set_in(children[quadrant], x % half, y % half, alive);
+        let [nw, ne, sw, se] = children;
+        self.join(nw, ne, sw, se)
+    }
+
+    // Número de células vivas
+    pub fn population(&self) -> u64 {
+        self.nodes[self.root as usize].population
+    }
+
+    // Nodos guardados (crece con la variedad del patrón, no con su tamaño)
+    pub fn node_count(&self) -> usize {
+        self.nodes.len()
+    }
+
+    // Rodear el nodo con espacio vacío: mismo centro, el doble de lado
+    fn expand(&mut self, node: NodeId) -> NodeId {
+        let level = self.nodes[node as usize].level;
+        let empty = self.empty(level - 1);
+        let [nw, ne, sw, se] = self.children(node);
+        let nw = self.join(empty, empty, empty, nw);
+        let ne = self.join(empty, empty, ne, empty);
+        let sw = self.join(empty, sw, empty, empty);
+        let se = self.join(se, empty, empty, empty);
+        self.join(nw, ne, sw, se)
+    }
+
+    // Indica si el nodo solo tiene células muertas (ni vivas ni paredes)
+    fn is_empty(&self, node: NodeId) -> bool {
+        let level = self.nodes[node as usize].level;
+        self.empty.get(level as usize) == Some(&node)
+    }
+
+    // Indica si todas las células vivas y las paredes están dentro del cuadrado central de
+    // lado 2^(nivel-1)
+    fn is_padded(&self, node: NodeId) -> bool {
+        let [nw, ne, sw, se] = self.children(node);
+        let inner = [(nw, 3), (ne, 2), (sw, 1), (se, 0)];
+        inner.iter().all(|&(child, keep)| {
+            self.children(child)
+                .iter()
+                .enumerate()
+                .all(|(quadrant, &grandchild)| quadrant == keep || self.is_empty(grandchild))
+        })
+    }
+
+    // Nodo central de la mitad de lado, sin avanzar
+    fn center(&mut self, node: NodeId) -> NodeId {
+        let [nw, ne, sw, se] = self.children(node);
+        let (nw, ne, sw, se) = (
+            self.children(nw)[3],
+            self.children(ne)[2],
+            self.children(sw)[1],
+            self.children(se)[0],
+        );
+        self.join(nw, ne, sw, se)
+    }
+
+    // Caso base: nodo de 4x4 células avanzado una generación, devuelve su centro de 2x2
+    fn step_leaf(&mut self, node: NodeId) -> NodeId {
+        let mut cells = [[DEAD; 4]; 4];
+        for (quadrant, &child) in self.children(node).iter().enumerate() {
+            for (cell, &value) in self.children(child).iter().enumerate() {
+                let x = (quadrant % 2) * 2 + cell % 2;
+                let y = (quadrant / 2) * 2 + cell / 2;
+                cells[y][x] = value;
+            }
+        }
+
+        // Índice de 9 bits igual al de la tabla de transiciones (NW es el bit más alto); las
+        // paredes no cambian
+        let next = |x: usize, y: usize| {
+            if cells[y][x] == WALL {
+                return WALL;
+            }
+            let mut index = 0;
+            for row in cells.iter().take(y + 2).skip(y - 1) {
+                for &cell in row.iter().take(x + 2).skip(x - 1) {
+                    index = (index << 1) | (cell == ALIVE) as usize;
+                }
+            }
+            debug_assert_eq!(index & CENTER != 0, cells[y][x] == ALIVE);
+            if self.table[index] { ALIVE } else { DEAD }
+        };
+        let (nw, ne, sw, se) = (next(1, 1), next(2, 1), next(1, 2), next(2, 2));
+        self.join(nw, ne, sw, se)
+    }
+
+    // Centro de un nodo de nivel L (lado 2^(L-1)) avanzado 2^step generaciones, step <= L-2
+    fn result(&mut self, node: NodeId, step: u8) -> NodeId {
+        if let Some(&result) = self.results.get(&(node, step)) {
+            return result;
+        }
+        let level = self.nodes[node as usize].level;
+        let result = if self.is_empty(node) {
+            self.empty(level - 1)
+        } else if level == 2 {
+            self.step_leaf(node)
+        } else {
+            // Los 9 subcuadrados de nivel L-1 que se solapan
+            let [a, b, c, d] = self.children(node);
+            let [_, a_ne, a_sw, a_se] = self.children(a);
+            let [b_nw, _, b_sw, b_se] = self.children(b);
+            let [c_nw, c_ne, _, c_se] = self.children(c);
+            let [d_nw, d_ne, d_sw, _] = self.children(d);
+            let squares = [
+                a,
+                self.join(a_ne, b_nw, a_se, b_sw),
+                b,
+                self.join(a_sw, a_se, c_nw, c_ne),
+                self.join(a_se, b_sw, c_ne, d_nw),
+                self.join(b_sw, b_se, d_nw, d_ne),
+                c,
+                self.join(c_ne, d_nw, c_se, d_sw),
+                d,
+            ];
+
+            // A velocidad máxima cada mitad del salto avanza 2^(L-3); con saltos menores
+            // la primera mitad solo toma el centro y la segunda avanza todo el salto
+            let full = step == level - 2;
+            let mut r = [DEAD; 9];
+            for (i, &square) in squares.iter().enumerate() {
+                r[i] = if full {
+                    self.result(square, level - 3)
+                } else {
+                    self.center(square)
+                };
+            }
+            let second = if full { level - 3 } else { step };
+            let nw = self.join(r[0], r[1], r[3], r[4]);
+            let ne = self.join(r[1], r[2], r[4], r[5]);
+            let sw = self.join(r[3], r[4], r[6], r[7]);
+            let se = self.join(r[4], r[5], r[7], r[8]);
+            let (nw, ne, sw, se) = (
+                self.result(nw, second),
+                self.result(ne, second),
+                self.result(sw, second),
+                self.result(se, second),
+            );
+            self.join(nw, ne, sw, se)
+        };
+        self.results.insert((node, step), result);
+        result
+    }
+
+    // Avanzar 2^step generaciones de una vez
+    pub fn step(&mut self, step: u8) {
+        let step = step.min(MAX_STEP_LOG);
+        if self.nodes.len() > COLLECT_THRESHOLD {
+            self.collect();
+        }
+        // is_empty reconoce los nodos vacíos comparándolos con los de self.empty
+        self.empty(self.level());
+
+        // El patrón debe quedar en el cuarto central para que nada de lo que crece en
+        // 2^step generaciones salga del centro que devuelve result
+        while self.level() < step + 3 || !self.is_padded(self.root) {
+            self.root = self.expand(self.root);
+        }
+        self.root = self.expand(self.root);
+        self.root = self.result(self.root, step);
+        self.generation += 1 << step;
+    }
+
+    // Descartar los nodos y resultados que ya no se usan copiando solo el árbol actual
+    fn collect(&mut self) {
+        let fresh = HashLife {
+            nodes: self.nodes[..3].to_vec(),
+            index: HashMap::new(),
+            results: HashMap::new(),
+            empty: vec![DEAD],
+            table: self.table.clone(),
+            root: DEAD,
+            generation: self.generation,
+        };
+        let old = std::mem::replace(self, fresh);
+        let mut copied = HashMap::new();
+        self.root = self.copy_from(&old, old.root, &mut copied);
+    }
+
+    fn copy_from(
+        &mut self,
+        old: &HashLife,
+        node: NodeId,
+        copied: &mut HashMap<NodeId, NodeId>,
+    ) -> NodeId {
+        if node <= WALL {
+            return node;
+        }
+        if let Some(&id) = copied.get(&node) {
+            return id;
+        }
+        let [nw, ne, sw, se] = old
+            .children(node)
+            .map(|child| self.copy_from(old, child, copied));
+        let id = self.join(nw, ne, sw, se);
+        copied.insert(node, id);
+        id
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Grid vacío de `width` x `height` con bordes muertos
+    fn empty_game(width: u32, height: u32) -> GameOfLife {
+        let mut game = GameOfLife::new(width, height);
+        game.clear_grid();
+        game
+    }
+
+    // Avanzar una copia del grid con step(step_log) y el grid original con 2^step_log updates
+    fn assert_matches_updates(mut game: GameOfLife, step_log: u8) {
+        let mut life = HashLife::from_game(&game).unwrap();
+        life.step(step_log);
+        let mut jumped = empty_game(game.width, game.height);
+        life.write_to_game(&mut jumped);
+        for _ in 0..1u32 << step_log {
+            game.update();
+        }
+        assert_eq!(jumped.generation, game.generation);
+        for y in 0..game.height as usize {
+            for x in 0..game.width as usize {
+                assert_eq!(jumped.get_cell(x, y), game.get_cell(x, y), "({x}, {y})");
+            }
+        }
+    }
+
+    // Planeador que avanza hacia abajo a la derecha, con la esquina en (x, y)
+    fn glider(game: &mut GameOfLife, x: usize, y: usize) {
+        for (dx, dy) in [(1, 0), (2, 1), (0, 2), (1, 2), (2, 2)] {
+            game.set_cell(x + dx, y + dy, true);
+        }
+    }
+
+    #[test]
+    fn glider_leaves_through_dead_edge() {
+        let mut game = empty_game(64, 64);
+        glider(&mut game, 60, 60);
+        assert_matches_updates(game, 4);
+    }
+
+    #[test]
+    fn glider_far_from_edges() {
+        let mut game = empty_game(256, 256);
+        glider(&mut game, 20, 20);
+        assert_matches_updates(game, 7);
+    }
+
+    #[test]
+    fn soup_matches_updates() {
+        let mut game = empty_game(48, 40);
+        for y in 0..40 {
+            for x in 0..48 {
+                game.set_cell(x, y, (x * 7 + y * 13 + x * y).is_multiple_of(3));
+            }
+        }
+        assert_matches_updates(game, 8);
+    }
+
+    #[test]
+    fn edge_patterns_jump_far() {
+        // Un bloque en la esquina y un parpadeador junto al borde: el patrón toca el marco
+        // de paredes y aun así un salto de 2^30 generaciones es inmediato
+        let mut game = empty_game(64, 64);
+        game.update();
+        let cells = [(0, 0), (1, 0), (0, 1), (1, 1), (1, 10), (1, 11), (1, 12)];
+        for (x, y) in cells {
+            game.set_cell(x, y, true);
+        }
+        let mut life = HashLife::from_game(&game).unwrap();
+        assert_eq!(life.generation, 1);
+        life.step(30);
+        assert_eq!(life.generation, 1 + (1 << 30));
+        life.write_to_game(&mut game);
+        assert_eq!(game.generation, 1 + (1 << 30));
+        assert_eq!(game.get_cell_age(0, 0), Some(0));
+        for y in 0..64 {
+            for x in 0..64 {
+                assert_eq!(game.get_cell(x, y), cells.contains(&(x, y)), "({x}, {y})");
+            }
+        }
+    }
+
+    #[test]
+    fn wrapping_boundaries_are_unavailable() {
+        let mut game = empty_game(64, 64);
+        for boundary in [
+            Boundary::Torus,
+            Boundary::KleinBottle,
+            Boundary::CrossSurface,
+            Boundary::Mirror,
+        ] {
+            game.boundary = boundary;
+            assert!(HashLife::from_game(&game).is_none(), "{boundary:?}");
+        }
+        game.boundary = Boundary::Dead;
+        assert!(HashLife::from_game(&game).is_some());
+    }
+}
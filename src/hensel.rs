@@ -0,0 +1,306 @@
+use crate::rule::RuleParseError;
+
+// Notación de Hensel para reglas isotrópicas no totalísticas ("B2-a/S12", "B3/S2-i34q").
+// Cada letra identifica una disposición de los vecinos salvo rotaciones y reflexiones.
+
+// Configuración representativa de cada letra para 1 a 4 vecinos, como índice de 9 bits
+// de la vecindad 3x3 (mismos valores que Golly). Los conteos 5 a 7 usan el complemento
+// de la misma letra con 8 - n vecinos.
+const NEIGHBORHOODS: [&[u16]; 5] = [
+    &[0],
+    &[1, 2],
+    &[5, 10, 3, 40, 33, 68],
+    &[69, 42, 11, 7, 98, 13, 14, 70, 41, 97],
+    &[325, 170, 15, 45, 99, 71, 106, 102, 43, 101, 105, 78, 108],
+];
+
+// Bit de la célula central en el índice de la vecindad
+pub const CENTER: usize = 1 << 4;
+
+// Los 8 vecinos (todos los bits excepto el central)
+const RING: u16 = 0x1FF & !(CENTER as u16);
+
+// Letras válidas para cada número de vecinos, en el orden canónico de Golly
+pub fn letters(count: usize) -> &'static str {
+    match count.min(8 - count) {
+        0 => "",
+        1 => "ce",
+        2 => "ceaikn",
+        3 => "ceaiknjqry",
+        _ => "ceaiknjqrtwyz",
+    }
+}
+
+// Máscara con todas las disposiciones de un conteo activadas
+pub fn full_mask(count: usize) -> u16 {
+    match letters(count).len() {
+        0 => 1, // 0 y 8 vecinos solo tienen una disposición
+        len => (1 << len) - 1,
+    }
+}
+
+// Vecindad representativa de la k-ésima letra de un conteo
+fn neighborhood(count: usize, letter: usize) -> u16 {
+    let base = NEIGHBORHOODS[count.min(8 - count)][letter];
+    if count > 4 { !base & RING } else { base }
+}
+
+// Transformar una vecindad moviendo cada celda (fila, columna) con `map`
+fn transform(index: u16, map: impl Fn(usize, usize) -> (usize, usize)) -> u16 {
+    (0..9)
+        .filter(|bit| index & (1 << bit) != 0)
+        .map(|bit| {
+            let (row, column) = map(bit / 3, bit % 3);
+            1 << (row * 3 + column)
+        })
+        .sum()
+}
+
+// Las 8 simetrías del cuadrado: rotaciones de 90° y sus reflexiones
+fn symmetries(index: u16) -> [usize; 8] {
+    let mut variants = [0; 8];
+    let mut current = index;
+    let mut mirror = transform(index, |row, column| (row, 2 - column));
+    for rotation in 0..4 {
+        variants[rotation * 2] = current as usize;
+        variants[rotation * 2 + 1] = mirror as usize;
+        current = transform(current, |row, column| (column, 2 - row));
+        mirror = transform(mirror, |row, column| (column, 2 - row));
+    }
+    variants
+}
+
+// Recorrer cada disposición (conteo, máscara de su letra, índices equivalentes)
+fn for_each_arrangement(mut visit: impl FnMut(usize, u16, &[usize; 8])) {
+    for count in 0..=8 {
+        let arrangements = letters(count).len().max(1);
+        for letter in 0..arrangements {
+            visit(count, 1 << letter, &symmetries(neighborhood(count, letter)));
+        }
+    }
+}
+
+// Interpretar una sección como "2-a" o "2i34q" y devolver una máscara de letras por conteo
+pub fn parse_section(section: char, text: &str) -> Result<[u16; 9], RuleParseError> {
+    let mut masks = [0u16; 9];
+    let mut current: Option<usize> = None;
+    let mut negated = false;
+    let mut has_letters = false;
+
+    // Un '-' sin letras después ("3-" o "3-4") no niega ninguna disposición
+    let dangling = RuleParseError::InvalidCharacter {
+        section,
+        found: '-',
+    };
+
+    for found in text.chars() {
+        if let Some(n) = found.to_digit(10) {
+            if negated && !has_letters {
+                return Err(dangling);
+            }
+            if n > 8 {
+                return Err(RuleParseError::InvalidCharacter { section, found });
+            }
+            current = Some(n as usize);
+            masks[n as usize] = full_mask(n as usize);
+            negated = false;
+            has_letters = false;
+            continue;
+        }
+
+        let Some(count) = current else {
+            return Err(RuleParseError::InvalidCharacter { section, found });
+        };
+        if found == '-' && !has_letters && !negated {
+            negated = true;
+            continue;
+        }
+        let Some(bit) = letters(count).find(found) else {
+            return Err(RuleParseError::InvalidHensel {
+                section,
+                count: count as u8,
+                letter: found,
+            });
+        };
+
+        // La primera letra sin '-' reemplaza el conteo completo por solo esa disposición
+        if !negated && !has_letters {
+            masks[count] = 0;
+        }
+        has_letters = true;
+        if negated {
+            masks[count] &= !(1 << bit);
+        } else {
+            masks[count] |= 1 << bit;
+        }
+    }
+    if negated && !has_letters {
+        return Err(dangling);
+    }
+
+    Ok(masks)
+}
+
+// Indica si todas las máscaras activan todas o ninguna disposición
+pub fn is_totalistic(masks: &[u16; 9]) -> bool {
+    masks
+        .iter()
+        .enumerate()
+        .all(|(count, &mask)| mask == 0 || mask == full_mask(count))
+}
+
+// Construir la tabla de 512 entradas a partir de las máscaras de nacimiento y supervivencia
+pub fn build_table(birth: &[u16; 9], survival: &[u16; 9]) -> Box<[bool; 512]> {
+    let mut table = Box::new([false; 512]);
+    for_each_arrangement(|count, bit, variants| {
+        for &variant in variants {
+            let index = variant;
+            table[index] = birth[count] & bit != 0;
+            table[index | CENTER] = survival[count] & bit != 0;
+        }
+    });
+    table
+}
+
+// Indica si la tabla trata igual todas las rotaciones y reflexiones de cada vecindad
+pub fn is_isotropic(table: &[bool; 512]) -> bool {
+    let mut isotropic = true;
+    for_each_arrangement(|_, _, variants| {
+        for center in [0, CENTER] {
+            let expected = table[variants[0] | center];
+            isotropic &= variants
+                .iter()
+                .all(|&variant| table[variant | center] == expected);
+        }
+    });
+    isotropic
+}
+
+// Escribir la sección de nacimiento o supervivencia de una tabla isotrópica en notación de Hensel
+pub fn format_section(table: &[bool; 512], center: bool) -> String {
+    let center = if center { CENTER } else { 0 };
+    let mut masks = [0u16; 9];
+    for_each_arrangement(|count, bit, variants| {
+        if table[variants[0] | center] {
+            masks[count] |= bit;
+        }
+    });
+
+    let mut text = String::new();
+    for (count, &mask) in masks.iter().enumerate() {
+        if mask == 0 {
+            continue;
+        }
+        text.push(char::from(b'0' + count as u8));
+        if mask == full_mask(count) {
+            continue;
+        }
+
+        // Usar la forma con '-' cuando es más corta
+        let letters = letters(count);
+        let enabled = mask.count_ones() as usize;
+        let negated = enabled * 2 > letters.len();
+        if negated {
+            text.push('-');
+        }
+        for (bit, letter) in letters.chars().enumerate() {
+            if (mask & (1 << bit) != 0) != negated {
+                text.push(letter);
+            }
+        }
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rule::Rule;
+
+    #[test]
+    fn letters_partition_every_neighborhood() {
+        // Cada vecindad de n vecinos pertenece exactamente a una letra de n
+        let mut owner = [None; 512];
+        for_each_arrangement(|count, bit, variants| {
+            for &variant in variants {
+                assert_eq!(
+                    (variant & !CENTER).count_ones() as usize,
+                    count,
+                    "{variant}"
+                );
+                assert!(
+                    owner[variant].is_none_or(|previous| previous == (count, bit)),
+                    "{variant}"
+                );
+                owner[variant] = Some((count, bit));
+            }
+        });
+        for (index, owner) in owner.iter().enumerate() {
+            assert_eq!(owner.is_some(), index & CENTER == 0, "{index}");
+        }
+    }
+
+    #[test]
+    fn letter_counts_match_golly() {
+        let classes: Vec<usize> = (0..=8).map(|count| letters(count).len().max(1)).collect();
+        assert_eq!(classes, [1, 2, 6, 10, 13, 10, 6, 2, 1]);
+    }
+
+    #[test]
+    fn hensel_rules_round_trip() {
+        for rule in [
+            "B3/S2-i34q",
+            "B2-a/S12",
+            "B2e3-anq/S23",
+            "B3aij/S4w5y",
+            "B2c3a4e5i6k7c/S1e2n3q4t5r6a7e8",
+            "B3/S2-i34q/C4",
+        ] {
+            let parsed: Rule = rule.parse().unwrap_or_else(|err| panic!("{rule}: {err}"));
+            assert!(parsed.table.is_some(), "{rule}");
+            let printed = parsed.to_string();
+            assert_eq!(printed.parse::<Rule>(), Ok(parsed), "{rule} -> {printed}");
+        }
+        // Todas las letras de un conteo equivalen al conteo sin letras
+        assert_eq!("B3/S2ceaikn3".parse::<Rule>(), Ok(Rule::conway()));
+    }
+
+    #[test]
+    fn tables_are_isotropic() {
+        let masks = parse_section('B', "2-a3aij4w").unwrap();
+        let table = build_table(&masks, &[0; 9]);
+        assert!(is_isotropic(&table));
+        let mut broken = table.clone();
+        broken[1] = !broken[1];
+        assert!(!is_isotropic(&broken));
+        assert_eq!(format_section(&table, false), "2-a3aij4w");
+    }
+
+    #[test]
+    fn invalid_letters() {
+        assert!(matches!(
+            parse_section('B', "1a"),
+            Err(RuleParseError::InvalidHensel {
+                section: 'B',
+                count: 1,
+                letter: 'a'
+            })
+        ));
+        assert!(matches!(
+            parse_section('S', "a"),
+            Err(RuleParseError::InvalidCharacter { .. })
+        ));
+        // Un '-' sin letras después, al final o antes de otro conteo
+        for (section, text) in [('B', "3-"), ('S', "5-2"), ('S', "2-i3-")] {
+            assert_eq!(
+                parse_section(section, text),
+                Err(RuleParseError::InvalidCharacter {
+                    section,
+                    found: '-'
+                }),
+                "{text}"
+            );
+        }
+        assert!("B3-/S23".parse::<Rule>().is_err());
+    }
+}
@@ -0,0 +1,430 @@
+use crate::game_of_life::{CellColor, ColorSum, Hsv, Hue};
+use crate::random::Rng;
+use raylib::prelude::Color;
+use std::sync::Arc;
+
+// Estrategias para elegir el color de una célula que nace a partir de sus padres (los
+// vecinos vivos con color). El promedio circular mezcla los matices y con el tiempo las
+// zonas de varios colores se vuelven de un color intermedio; las demás estrategias
+// conservan los territorios de color de distintas formas.
+
+// Vecino vivo con color de una célula que nace
+#[derive(Clone, Copy, Debug)]
+pub struct Parent {
+    pub color: CellColor,
+    pub weight: u32, // Peso del vecino (1, o el peso de la célula del kernel)
+    pub age: u64,    // Generaciones que lleva viva desde que nació
+}
+
+// Forma de heredar el color. Se llama desde varios hilos a la vez (una banda de filas por
+// hilo), así el resultado solo debe depender de los padres y de `seed`.
+pub trait ColorInheritance: Send + Sync {
+    // Nombre para mostrar
+    fn name(&self) -> &str;
+
+    // Color de la célula que nace (None si no hay padres: nace con un color al azar).
+    // `seed` es un número pseudoaleatorio que depende solo de la célula.
+    fn inherit(&self, parents: &mut dyn Iterator<Item = Parent>, seed: u64) -> Option<Hsv>;
+
+    // Indica que la estrategia es el promedio circular ponderado: las reglas con
+    // vecindades grandes lo calculan con las tablas de áreas sumadas, sin recorrer los padres
+    fn is_circular_mean(&self) -> bool {
+        false
+    }
+}
+
+// Promedio circular de los matices y promedio de la saturación y el brillo, ponderados
+// por el peso de cada padre (por defecto)
+pub struct CircularMean;
+
+impl ColorInheritance for CircularMean {
+    fn name(&self) -> &str {
+        "promedio circular"
+    }
+
+    fn inherit(&self, parents: &mut dyn Iterator<Item = Parent>, _seed: u64) -> Option<Hsv> {
+        let mut sum = ColorSum::default();
+        for parent in parents {
+            sum.add(&parent.color, parent.weight);
+        }
+        sum.average()
+    }
+
+    fn is_circular_mean(&self) -> bool {
+        true
+    }
+}
+
+// Número de grupos de matiz de MajorityBucket
+const BUCKETS: usize = 12;
+
+// El círculo de matices se parte en BUCKETS grupos; gana el grupo con más peso y la
+// célula toma el promedio de los colores de ese grupo. Los colores no se mezclan entre
+// grupos, así las fronteras entre territorios quedan marcadas.
+pub struct MajorityBucket;
+
+impl ColorInheritance for MajorityBucket {
+    fn name(&self) -> &str {
+        "grupo de matiz mayoritario"
+    }
+
+    fn inherit(&self, parents: &mut dyn Iterator<Item = Parent>, _seed: u64) -> Option<Hsv> {
+        let mut buckets: [ColorSum; BUCKETS] = Default::default();
+        for parent in parents {
+            let bucket = ((parent.color.hsv.hue.value * BUCKETS as f32) as usize).min(BUCKETS - 1);
+            buckets[bucket].add(&parent.color, parent.weight);
+        }
+        // En un empate gana el primer grupo
+        let best = buckets
+            .iter()
+            .enumerate()
+            .max_by_key(|&(bucket, sum)| (sum.weight(), std::cmp::Reverse(bucket)))?
+            .1;
+        best.average()
+    }
+}
+
+// La célula copia el color de un padre elegido al azar, con probabilidad proporcional a su peso
+pub struct RandomParent;
+
+impl ColorInheritance for RandomParent {
+    fn name(&self) -> &str {
+        "padre al azar"
+    }
+
+    fn inherit(&self, parents: &mut dyn Iterator<Item = Parent>, seed: u64) -> Option<Hsv> {
+        // Muestreo de reservorio: el padre k reemplaza al elegido con probabilidad
+        // peso / peso acumulado, sin guardar la lista de padres
+        let mut rng = Rng::new(seed);
+        let mut total = 0u64;
+        let mut chosen = None;
+        for parent in parents.filter(|parent| parent.weight > 0) {
+            total += parent.weight as u64;
+            if rng.next_u64() % total < parent.weight as u64 {
+                chosen = Some(parent.color.hsv);
+            }
+        }
+        chosen
+    }
+}
+
+// La célula copia el color del padre más viejo (en un empate, el de más peso y luego el
+// primero): los colores de las estructuras estables dominan sobre los de las que cambian
+pub struct OldestParent;
+
+impl ColorInheritance for OldestParent {
+    fn name(&self) -> &str {
+        "padre más viejo"
+    }
+
+    fn inherit(&self, parents: &mut dyn Iterator<Item = Parent>, _seed: u64) -> Option<Hsv> {
+        let mut oldest: Option<Parent> = None;
+        for parent in parents {
+            if oldest.is_none_or(|best| (parent.age, parent.weight) > (best.age, best.weight)) {
+                oldest = Some(parent);
+            }
+        }
+        oldest.map(|parent| parent.color.hsv)
+    }
+}
+
+// Mezcla de los colores en RGB
+pub struct RgbBlend;
+
+impl ColorInheritance for RgbBlend {
+    fn name(&self) -> &str {
+        "mezcla RGB"
+    }
+
+    fn inherit(&self, parents: &mut dyn Iterator<Item = Parent>, _seed: u64) -> Option<Hsv> {
+        blend(
+            parents,
+            |color| [color.r, color.g, color.b].map(|channel| channel as f32 / 255.0),
+            |rgb| rgb,
+        )
+    }
+}
+
+// Mezcla de los colores en el espacio perceptual OKLab
+pub struct LabBlend;
+
+impl ColorInheritance for LabBlend {
+    fn name(&self) -> &str {
+        "mezcla Lab"
+    }
+
+    fn inherit(&self, parents: &mut dyn Iterator<Item = Parent>, _seed: u64) -> Option<Hsv> {
+        blend(parents, srgb_to_oklab, oklab_to_srgb)
+    }
+}
+
+// Estrategias incluidas, en el orden en que se recorren con la tecla C
+pub fn builtin() -> Vec<Arc<dyn ColorInheritance>> {
+    vec![
+        Arc::new(CircularMean),
+        Arc::new(MajorityBucket),
+        Arc::new(RandomParent),
+        Arc::new(OldestParent),
+        Arc::new(RgbBlend),
+        Arc::new(LabBlend),
+    ]
+}
+
+// Promedio ponderado de los colores en otro espacio (`to_space` y `from_space` convierten
+// desde y hacia RGB entre 0 y 1). Si la mezcla queda gris, sin matiz, se usa el del primer padre.
+fn blend(
+    parents: &mut dyn Iterator<Item = Parent>,
+    to_space: impl Fn(Color) -> [f32; 3],
+    from_space: impl Fn([f32; 3]) -> [f32; 3],
+) -> Option<Hsv> {
+    let mut sum = [0.0f32; 3];
+    let mut weight = 0u32;
+    let mut first = None;
+    for parent in parents {
+        let components = to_space(parent.color.color);
+        for (total, component) in sum.iter_mut().zip(components) {
+            *total += parent.weight as f32 * component;
+        }
+        weight += parent.weight;
+        first.get_or_insert(parent.color.hsv);
+    }
+    let first = first?;
+    if weight == 0 {
+        return Some(first);
+    }
+    Some(rgb_to_hsv(from_space(sum.map(|total| total / weight as f32)), first.hue))
+}
+
+// Color HSV de un color RGB entre 0 y 1 (los grises, sin matiz, toman el matiz `gray_hue`)
+fn rgb_to_hsv([r, g, b]: [f32; 3], gray_hue: Hue) -> Hsv {
+    let max = r.max(g).max(b);
+    let chroma = max - r.min(g).min(b);
+    if chroma <= 1e-4 {
+        return Hsv::new(gray_hue, 0.0, max);
+    }
+    let sector = if max == r {
+        (g - b) / chroma
+    } else if max == g {
+        (b - r) / chroma + 2.0
+    } else {
+        (r - g) / chroma + 4.0
+    };
+    Hsv::new(Hue::new(sector / 6.0), chroma / max, max)
+}
+
+// Conversión de sRGB a OKLab (L, a, b)
+fn srgb_to_oklab(color: Color) -> [f32; 3] {
+    let linear = |channel: u8| {
+        let c = channel as f32 / 255.0;
+        if c <= 0.04045 {
+            c / 12.92
+        } else {
+            ((c + 0.055) / 1.055).powf(2.4)
+        }
+    };
+    let (r, g, b) = (linear(color.r), linear(color.g), linear(color.b));
+    let l = (0.412_221_46 * r + 0.536_332_55 * g + 0.051_445_995 * b).cbrt();
+    let m = (0.211_903_5 * r + 0.680_699_5 * g + 0.107_396_96 * b).cbrt();
+    let s = (0.088_302_46 * r + 0.281_718_85 * g + 0.629_978_7 * b).cbrt();
+    [
+        0.210_454_26 * l + 0.793_617_8 * m - 0.004_072_047 * s,
+        1.977_998_5 * l - 2.428_592_2 * m + 0.450_593_7 * s,
+        0.025_904_037 * l + 0.782_771_77 * m - 0.808_675_77 * s,
+    ]
+}
+
+// Conversión de OKLab a sRGB entre 0 y 1 (los colores fuera de la gama se recortan)
+fn oklab_to_srgb([lightness, a, b]: [f32; 3]) -> [f32; 3] {
+    let l = (lightness + 0.396_337_78 * a + 0.215_803_76 * b).powi(3);
+    let m = (lightness - 0.105_561_346 * a - 0.063_854_17 * b).powi(3);
+    let s = (lightness - 0.089_484_18 * a - 1.291_485_5 * b).powi(3);
+    let gamma = |c: f32| {
+        let c = c.clamp(0.0, 1.0);
+        if c <= 0.003_130_8 {
+            12.92 * c
+        } else {
+            1.055 * c.powf(1.0 / 2.4) - 0.055
+        }
+    };
+    [
+        gamma(4.076_741_7 * l - 3.307_711_6 * m + 0.230_969_94 * s),
+        gamma(-1.268_438 * l + 2.609_757_4 * m - 0.341_319_38 * s),
+        gamma(-0.004_196_086_3 * l - 0.703_418_6 * m + 1.707_614_7 * s),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parent(hue: f32, weight: u32, age: u64) -> Parent {
+        Parent {
+            color: CellColor::new(Hue::new(hue).into()),
+            weight,
+            age,
+        }
+    }
+
+    fn inherit(strategy: &dyn ColorInheritance, parents: &[Parent], seed: u64) -> Option<Hsv> {
+        strategy.inherit(&mut parents.iter().copied(), seed)
+    }
+
+    fn hue(strategy: &dyn ColorInheritance, parents: &[Parent], seed: u64) -> f32 {
+        inherit(strategy, parents, seed).unwrap().hue.value
+    }
+
+    fn assert_close(found: f32, expected: f32) {
+        assert!((found - expected).abs() < 1e-3, "{found} != {expected}");
+    }
+
+    #[test]
+    fn no_parents() {
+        for strategy in builtin() {
+            assert!(inherit(&*strategy, &[], 1).is_none(), "{}", strategy.name());
+        }
+    }
+
+    #[test]
+    fn circular_mean_weights() {
+        // 2 * (1, 0) + 1 * (0, 1): el matiz 0.0 pesa el doble que 0.25
+        let parents = [parent(0.0, 2, 0), parent(0.25, 1, 0)];
+        assert_close(
+            hue(&CircularMean, &parents, 0),
+            1f32.atan2(2.0) / std::f32::consts::TAU,
+        );
+        // El promedio pasa por el 0: 0.9 y 0.1 dan 0.0, no 0.5
+        let hue = hue(&CircularMean, &[parent(0.9, 1, 0), parent(0.1, 1, 0)], 0);
+        assert!(hue.min(1.0 - hue) < 1e-3, "{hue}");
+    }
+
+    #[test]
+    fn majority_bucket() {
+        // Dos padres en el grupo 0 ganan a uno en el grupo 6 y se promedian entre ellos
+        let parents = [parent(0.0, 1, 0), parent(0.5, 1, 0), parent(0.02, 1, 0)];
+        assert_close(hue(&MajorityBucket, &parents, 0), 0.01);
+        // Con pesos de kernel gana el grupo con más peso, no con más padres
+        let parents = [parent(0.0, 1, 0), parent(0.5, 3, 0), parent(0.02, 1, 0)];
+        assert_close(hue(&MajorityBucket, &parents, 0), 0.5);
+        // En un empate gana el primer grupo del círculo, sin importar el orden de los padres
+        for parents in [
+            [parent(0.5, 2, 0), parent(0.25, 2, 0)],
+            [parent(0.25, 2, 0), parent(0.5, 2, 0)],
+        ] {
+            assert_close(hue(&MajorityBucket, &parents, 0), 0.25);
+        }
+    }
+
+    #[test]
+    fn random_parent() {
+        let hues = [0.1, 0.4, 0.7, 0.9];
+        let parents: Vec<_> = hues
+            .iter()
+            .zip([1, 2, 0, 1])
+            .map(|(&hue, weight)| parent(hue, weight, 0))
+            .collect();
+
+        // Con la semilla 42 el reservorio elige el padre 0 (1 de 1), lo reemplaza por el 1
+        // (2 de 3), ignora el 2 (peso 0) y se queda con el 1 (el 3 necesita 1 de 4)
+        let mut rng = Rng::new(42);
+        rng.next_u64(); // El primer padre con peso siempre se elige
+        assert!(rng.next_u64() % 3 < 2);
+        assert!(rng.next_u64() % 4 >= 1);
+        assert_eq!(hue(&RandomParent, &parents, 42), 0.4);
+        // La misma semilla da siempre el mismo padre
+        assert_eq!(
+            hue(&RandomParent, &parents, 42),
+            hue(&RandomParent, &parents, 42)
+        );
+
+        // La probabilidad es proporcional al peso y el padre de peso 0 nunca sale
+        let mut counts = [0u32; 4];
+        for seed in 0..4000 {
+            let hue = hue(&RandomParent, &parents, seed);
+            counts[hues.iter().position(|&h| h == hue).unwrap()] += 1;
+        }
+        assert_eq!(counts[2], 0);
+        for (count, expected) in [(counts[0], 1000), (counts[1], 2000), (counts[3], 1000)] {
+            assert!(count.abs_diff(expected) < 150, "{counts:?}");
+        }
+    }
+
+    #[test]
+    fn oldest_parent() {
+        // Gana el más viejo; entre los de la misma edad, el de más peso y luego el primero
+        let parents = [
+            parent(0.1, 1, 3),
+            parent(0.2, 1, 7),
+            parent(0.3, 2, 7),
+            parent(0.4, 2, 7),
+        ];
+        assert_eq!(hue(&OldestParent, &parents, 0), 0.3);
+        assert_eq!(hue(&OldestParent, &parents[..2], 0), 0.2);
+    }
+
+    #[test]
+    fn rgb_blend() {
+        // Rojo y verde con el mismo peso: (0.5, 0.5, 0) es amarillo oscuro
+        let hsv = inherit(&RgbBlend, &[parent(0.0, 1, 0), parent(1.0 / 3.0, 1, 0)], 0).unwrap();
+        assert_close(hsv.hue.value, 1.0 / 6.0);
+        assert_close(hsv.saturation, 1.0);
+        assert_close(hsv.value, 0.5);
+        // Con el rojo pesando el triple: (0.75, 0.25, 0)
+        let hsv = inherit(&RgbBlend, &[parent(0.0, 3, 0), parent(1.0 / 3.0, 1, 0)], 0).unwrap();
+        assert_close(hsv.hue.value, 0.25 / 0.75 / 6.0);
+        assert_close(hsv.saturation, 1.0);
+        assert_close(hsv.value, 0.75);
+    }
+
+    #[test]
+    fn gray_blends_keep_first_hue() {
+        let gray = |hue, value| CellColor::new(Hsv::new(Hue::new(hue), 0.0, value));
+        for (first, second) in [(0.0, 0.5), (0.5, 0.0)] {
+            // Colores opuestos en RGB dan gris: sin saturación y con el matiz del primer padre
+            let hsv = inherit(&RgbBlend, &[parent(first, 1, 0), parent(second, 1, 0)], 0).unwrap();
+            assert_eq!(
+                (hsv.hue.value, hsv.saturation),
+                (first, 0.0),
+                "RGB {first} {second}"
+            );
+            // En OKLab los opuestos del círculo HSV no se anulan: se mezclan blanco y negro
+            let mut parents = [parent(first, 1, 0), parent(second, 1, 0)];
+            parents[0].color = gray(first, 1.0);
+            parents[1].color = gray(second, 0.0);
+            let hsv = inherit(&LabBlend, &parents, 0).unwrap();
+            assert_eq!(
+                (hsv.hue.value, hsv.saturation),
+                (first, 0.0),
+                "Lab {first} {second}"
+            );
+        }
+        // Sin peso total no hay promedio: se copia el primer padre
+        let parents = [parent(0.3, 0, 0), parent(0.6, 0, 0)];
+        assert_eq!(hue(&RgbBlend, &parents, 0), 0.3);
+        assert_eq!(hue(&LabBlend, &parents, 0), 0.3);
+    }
+
+    #[test]
+    fn lab_blend_round_trip() {
+        // Un solo color sale igual de OKLab, y dos copias con cualquier peso también
+        for hue in [0.0, 0.1, 1.0 / 3.0, 0.6, 0.8] {
+            for parents in [
+                vec![parent(hue, 1, 0)],
+                vec![parent(hue, 2, 0), parent(hue, 5, 0)],
+            ] {
+                let hsv = inherit(&LabBlend, &parents, 0).unwrap();
+                let expected = Hsv::from(Hue::new(hue)).to_color();
+                let color = hsv.to_color();
+                for (found, expected) in [
+                    (color.r, expected.r),
+                    (color.g, expected.g),
+                    (color.b, expected.b),
+                ] {
+                    assert!(
+                        found.abs_diff(expected) <= 2,
+                        "{hue}: {found} != {expected}"
+                    );
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,287 @@
+use std::fmt;
+
+// Vecindad personalizada: un conjunto de desplazamientos con un peso entero cada uno.
+// El número de vecinos de una célula es la suma de los pesos de las células vivas.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Kernel {
+    pub cells: Vec<KernelCell>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct KernelCell {
+    pub dx: i32,
+    pub dy: i32,
+    pub weight: u32,
+}
+
+// Errores al leer un kernel desde una cuadrícula de texto
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum KernelParseError {
+    Empty,
+    EvenSize {
+        width: usize,
+        height: usize,
+    },
+    RaggedRow {
+        row: usize,
+        expected: usize,
+        found: usize,
+    },
+    InvalidCharacter {
+        row: usize,
+        column: usize,
+        found: char,
+    },
+}
+
+impl fmt::Display for KernelParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            KernelParseError::Empty => write!(f, "el kernel está vacío"),
+            KernelParseError::EvenSize { width, height } => write!(
+                f,
+                "el kernel mide {width}x{height}: el ancho y el alto deben ser impares para tener centro"
+            ),
+            KernelParseError::RaggedRow {
+                row,
+                expected,
+                found,
+            } => write!(
+                f,
+                "la fila {row} del kernel tiene {found} columnas y se esperaban {expected}"
+            ),
+            KernelParseError::InvalidCharacter { row, column, found } => write!(
+                f,
+                "carácter inválido '{found}' en la fila {row}, columna {column}: se esperaba un peso del 0 al 9 o '.'"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for KernelParseError {}
+
+impl Kernel {
+    // Crear un kernel a partir de (dx, dy, peso); los desplazamientos repetidos suman sus pesos
+    pub fn from_offsets(offsets: impl IntoIterator<Item = (i32, i32, u32)>) -> Self {
+        let mut cells: Vec<KernelCell> = Vec::new();
+        for (dx, dy, weight) in offsets {
+            match cells.iter_mut().find(|cell| cell.dx == dx && cell.dy == dy) {
+                Some(cell) => cell.weight += weight,
+                None => cells.push(KernelCell { dx, dy, weight }),
+            }
+        }
+        cells.retain(|cell| cell.weight > 0);
+        Kernel { cells }
+    }
+
+    // Cuadrado de lado 2r+1 sin la célula central
+    pub fn moore(radius: i32) -> Self {
+        Self::from_offsets(
+            Self::square(radius)
+                .filter(|&(dx, dy)| (dx, dy) != (0, 0))
+                .map(|(dx, dy)| (dx, dy, 1)),
+        )
+    }
+
+    // Rombo |dx| + |dy| <= r sin la célula central
+    pub fn von_neumann(radius: i32) -> Self {
+        Self::from_offsets(
+            Self::square(radius)
+                .filter(|&(dx, dy)| (dx, dy) != (0, 0) && dx.abs() + dy.abs() <= radius)
+                .map(|(dx, dy)| (dx, dy, 1)),
+        )
+    }
+
+    // Cruz: solo la fila y la columna de la célula, hasta distancia r
+    pub fn cross(radius: i32) -> Self {
+        Self::from_offsets(
+            Self::square(radius)
+                .filter(|&(dx, dy)| (dx, dy) != (0, 0) && (dx == 0 || dy == 0))
+                .map(|(dx, dy)| (dx, dy, 1)),
+        )
+    }
+
+    // Las 8 casillas a un salto de caballo de ajedrez
+    pub fn knight() -> Self {
+        Self::from_offsets(
+            Self::square(2)
+                .filter(|&(dx, dy)| dx.abs() * dy.abs() == 2)
+                .map(|(dx, dy)| (dx, dy, 1)),
+        )
+    }
+
+    // Leer un kernel desde una cuadrícula de pesos, por ejemplo:
+    //   "121\n2.2\n121" (bordes con peso 2, esquinas con peso 1)
+    // La célula central es el centro de la cuadrícula; '.' equivale a peso 0.
+    pub fn from_grid(text: &str) -> Result<Self, KernelParseError> {
+        let rows: Vec<&str> = text
+            .lines()
+            .map(str::trim)
+            .filter(|row| !row.is_empty())
+            .collect();
+        let Some(first) = rows.first() else {
+            return Err(KernelParseError::Empty);
+        };
+
+        let width = first.chars().count();
+        let height = rows.len();
+        if width % 2 != 1 || height % 2 != 1 {
+            return Err(KernelParseError::EvenSize { width, height });
+        }
+
+        let mut offsets = Vec::new();
+        for (row, line) in rows.iter().enumerate() {
+            let found = line.chars().count();
+            if found != width {
+                return Err(KernelParseError::RaggedRow {
+                    row,
+                    expected: width,
+                    found,
+                });
+            }
+            for (column, cell) in line.chars().enumerate() {
+                let weight = match cell {
+                    '.' => 0,
+                    _ => cell
+                        .to_digit(10)
+                        .ok_or(KernelParseError::InvalidCharacter {
+                            row,
+                            column,
+                            found: cell,
+                        })?,
+                };
+                let dx = column as i32 - (width / 2) as i32;
+                let dy = row as i32 - (height / 2) as i32;
+                offsets.push((dx, dy, weight));
+            }
+        }
+
+        Ok(Self::from_offsets(offsets))
+    }
+
+    // Suma máxima que puede alcanzar una célula (todas las casillas del kernel vivas)
+    pub fn max_sum(&self) -> u32 {
+        self.cells.iter().map(|cell| cell.weight).sum()
+    }
+
+    // Distancia máxima a la que llega el kernel en cualquier eje
+    pub fn radius(&self) -> i32 {
+        self.cells
+            .iter()
+            .map(|cell| cell.dx.abs().max(cell.dy.abs()))
+            .max()
+            .unwrap_or(0)
+    }
+
+    fn square(radius: i32) -> impl Iterator<Item = (i32, i32)> {
+        (-radius..=radius).flat_map(move |dy| (-radius..=radius).map(move |dx| (dx, dy)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Desplazamientos y pesos ordenados, para comparar sin depender del orden de construcción
+    fn offsets(kernel: &Kernel) -> Vec<(i32, i32, u32)> {
+        let mut offsets: Vec<_> = kernel
+            .cells
+            .iter()
+            .map(|cell| (cell.dx, cell.dy, cell.weight))
+            .collect();
+        offsets.sort();
+        offsets
+    }
+
+    #[test]
+    fn predefined_shapes() {
+        assert_eq!(Kernel::moore(1).cells.len(), 8);
+        assert_eq!(Kernel::moore(2).cells.len(), 24);
+        assert_eq!(Kernel::von_neumann(2).cells.len(), 12);
+        assert_eq!(
+            offsets(&Kernel::cross(2)),
+            [
+                (-2, 0, 1),
+                (-1, 0, 1),
+                (0, -2, 1),
+                (0, -1, 1),
+                (0, 1, 1),
+                (0, 2, 1),
+                (1, 0, 1),
+                (2, 0, 1)
+            ]
+        );
+        assert_eq!(
+            offsets(&Kernel::knight()),
+            [
+                (-2, -1, 1),
+                (-2, 1, 1),
+                (-1, -2, 1),
+                (-1, 2, 1),
+                (1, -2, 1),
+                (1, 2, 1),
+                (2, -1, 1),
+                (2, 1, 1)
+            ]
+        );
+        assert_eq!(Kernel::knight().radius(), 2);
+        assert_eq!(Kernel::cross(3).max_sum(), 12);
+    }
+
+    #[test]
+    fn grid_weights() {
+        let kernel = Kernel::from_grid("121\n2.2\n121").unwrap();
+        assert_eq!(
+            offsets(&kernel),
+            [
+                (-1, -1, 1),
+                (-1, 0, 2),
+                (-1, 1, 1),
+                (0, -1, 2),
+                (0, 1, 2),
+                (1, -1, 1),
+                (1, 0, 2),
+                (1, 1, 1)
+            ]
+        );
+        assert_eq!(kernel.max_sum(), 12);
+        assert_eq!(kernel.radius(), 1);
+
+        // El centro puede tener peso, los ceros no generan casillas y se ignoran las
+        // líneas vacías y los espacios de los bordes
+        let kernel = Kernel::from_grid("\n  0.3  \n 050\n...\n").unwrap();
+        assert_eq!(offsets(&kernel), [(0, 0, 5), (1, -1, 3)]);
+
+        // Los desplazamientos repetidos suman sus pesos
+        let kernel = Kernel::from_offsets([(1, 0, 2), (0, 1, 1), (1, 0, 3), (0, 1, 0)]);
+        assert_eq!(offsets(&kernel), [(0, 1, 1), (1, 0, 5)]);
+    }
+
+    #[test]
+    fn invalid_grids() {
+        assert_eq!(Kernel::from_grid(" \n\n"), Err(KernelParseError::Empty));
+        assert_eq!(
+            Kernel::from_grid("11\n11"),
+            Err(KernelParseError::EvenSize {
+                width: 2,
+                height: 2
+            })
+        );
+        assert_eq!(
+            Kernel::from_grid("111\n1.1\n11"),
+            Err(KernelParseError::RaggedRow {
+                row: 2,
+                expected: 3,
+                found: 2
+            })
+        );
+        assert_eq!(
+            Kernel::from_grid("111\n1x1\n111"),
+            Err(KernelParseError::InvalidCharacter {
+                row: 1,
+                column: 1,
+                found: 'x'
+            })
+        );
+    }
+}
@@ -6,6 +6,7 @@ pub fn line(
     framebuffer: &mut Framebuffer,
     start: Vector2,
     end: Vector2,
+    color: Color,
 ){
     let dx = (end.x - start.x).abs();
     let dy = (end.y - start.y).abs();
@@ -17,7 +18,7 @@ pub fn line(
     let mut y = start.y;
 
     while x != end.x || y != end.y {
-        framebuffer.set_pixel(x as u32, y as u32);
+        framebuffer.fill_rect(x as u32, y as u32, 1, 1, color);
         let err2 = err * 2.0;
         if err2 > -dy {
             err -= dy;
@@ -0,0 +1,97 @@
+use crate::bit_parallel::BitRule;
+use crate::bitgrid::BitGrid;
+
+// Motor de bloques con tabla precalculada, al estilo de QuickLife de Golly: cada bloque
+// de 4x4 células avanza leyendo su vecindad de 6x6 del grid con borde. La vecindad se
+// parte en cuatro ventanas de 4x4 que se solapan, y una tabla de 2^16 entradas da la
+// siguiente generación de las 2x2 células centrales de cada ventana.
+
+// Resultado de la regla para cada ventana de 4x4 células. El bit x + 4 * y del índice es
+// la célula (x, y) de la ventana; los bits 0, 1, 2 y 3 de la entrada son las células
+// centrales (1, 1), (2, 1), (1, 2) y (2, 2) en la siguiente generación.
+pub struct BlockTable {
+    rule: BitRule,
+    next: Vec<u8>,
+}
+
+impl BlockTable {
+    pub fn new(rule: &BitRule) -> Self {
+        let next = (0..1usize << 16)
+            .map(|window| {
+                let cell = |x: usize, y: usize| (window >> (x + 4 * y) & 1) as u32;
+                let mut result = 0;
+                for (bit, (cx, cy)) in [(1, 1), (2, 1), (1, 2), (2, 2)].into_iter().enumerate() {
+                    let mut count = 0;
+                    for y in cy - 1..=cy + 1 {
+                        for x in cx - 1..=cx + 1 {
+                            count += cell(x, y);
+                        }
+                    }
+                    let alive = cell(cx, cy) == 1;
+                    if rule.next_state(alive, count - alive as u32) {
+                        result |= 1 << bit;
+                    }
+                }
+                result
+            })
+            .collect();
+        BlockTable { rule: rule.clone(), next }
+    }
+
+    // Regla con la que se construyó la tabla
+    pub fn rule(&self) -> &BitRule {
+        &self.rule
+    }
+
+    // Siguiente generación de las 2x2 células centrales de una ventana de 4 filas de 4 bits
+    fn center(&self, rows: [u64; 4], shift: usize) -> u64 {
+        let window = rows
+            .iter()
+            .enumerate()
+            .fold(0, |window, (y, row)| window | ((row >> shift & 0xF) as usize) << (4 * y));
+        self.next[window] as u64
+    }
+}
+
+// Palabra `word` de la fila `y` del grid con borde junto con los bits de la siguiente
+// palabra (las filas fuera del grid cuentan como vacías)
+fn bits_at(padded: &BitGrid, y: usize, word: usize) -> u128 {
+    if y >= padded.height() {
+        return 0;
+    }
+    let row = padded.row(y);
+    let at = |k: usize| row.get(k).copied().unwrap_or(0) as u128;
+    at(word) | at(word + 1) << 64
+}
+
+// Calcular la palabra `word` de las filas `y` a `y + 3` de la siguiente generación a
+// partir del grid con borde de bit_parallel::pad (la célula (x, y) está en (x + 1, y + 1)).
+// Las filas después del final del grid dan resultados sin sentido que quien llama descarta.
+pub fn next_rows(padded: &BitGrid, table: &BlockTable, y: usize, word: usize) -> [u64; 4] {
+    // Las seis filas de la vecindad, de la fila y - 1 a la y + 4 del grid; la columna
+    // 64 * word del grid con borde es la célula de la izquierda de la primera del bloque
+    let rows: [u128; 6] = std::array::from_fn(|i| bits_at(padded, y + i, word));
+
+    let mut result = [0u64; 4];
+    for block in (0..64).step_by(4) {
+        let window = |first: usize| -> [u64; 4] {
+            std::array::from_fn(|i| (rows[first + i] >> block) as u64)
+        };
+        let (top, bottom) = (window(0), window(2));
+
+        // Cuatro ventanas de 4x4: arriba a la izquierda, arriba a la derecha, abajo a la
+        // izquierda y abajo a la derecha
+        let quadrants = [
+            table.center(top, 0),
+            table.center(top, 2),
+            table.center(bottom, 0),
+            table.center(bottom, 2),
+        ];
+        for (quadrant, &cells) in quadrants.iter().enumerate() {
+            let (dx, dy) = (2 * (quadrant % 2), 2 * (quadrant / 2));
+            result[dy] |= (cells & 0b11) << (block + dx);
+            result[dy + 1] |= (cells >> 2 & 0b11) << (block + dx);
+        }
+    }
+    result
+}
@@ -15,18 +15,53 @@ fn add_spaceship_lwss(game: &mut GameOfLife, x: usize, y: usize, hue: Hue) {
         }
     }
 }
-use std::thread;
-use std::time::Duration;
 
+use boundary::Boundary;
 use framebuffer::Framebuffer;
-use game_of_life::{GameOfLife, Hue};
+use game_of_life::{ColorMode, Engine, GameOfLife, Hue};
+use hashlife::HashLife;
+use kernel::Kernel;
+use palette::Palette;
 use raylib::prelude::*;
-
+use rule::{Rule, PRESETS};
+use scheduler::{Scheduler, Speed};
+use sparse::SparseLife;
+
+mod bench;
+mod bit_parallel;
+mod bitgrid;
+mod boundary;
 mod framebuffer;
 mod game_of_life;
+mod hashlife;
+mod hensel;
+mod inheritance;
+mod kernel;
 mod line;
+mod lookup;
+mod map_rule;
+mod palette;
+mod random;
+mod rule;
+mod scheduler;
+mod sparse;
+mod summed_area;
+
+// Velocidades que se recorren con la tecla V
+const SPEEDS: [(&str, Speed); 4] = [
+    ("10 generaciones por segundo", Speed::PerSecond(10.0)),
+    ("60 generaciones por segundo", Speed::PerSecond(60.0)),
+    ("sin límite", Speed::Unlimited),
+    ("1 generación por frame", Speed::PerFrame(1)),
+];
 
 fn main() {
+    // Medición de rendimiento sin abrir la ventana
+    if std::env::args().any(|arg| arg == "--bench") {
+        bench::run();
+        return;
+    }
+
     let window_width = 800;
     let window_height = 600;
 
@@ -44,16 +79,48 @@ fn main() {
         .title("Juego de la Vida de Conway - Jardin")
         .log_level(TraceLogLevel::LOG_WARNING)
         .build();
+    window.set_target_fps(60);
 
     let mut framebuffer = Framebuffer::new(framebuffer_width, framebuffer_height, Color::BLACK);
-    framebuffer.set_background_color(Color::BLACK);
 
-    // Crear el juego
-    let mut game = GameOfLife::new(game_width, game_height);
+    // Crear el juego. Con `--seed N` los colores al azar se repiten exactamente; sin
+    // semilla se toma una del reloj (se muestra abajo para poder repetir la simulación)
+    let seed = std::env::args()
+        .skip_while(|arg| arg != "--seed")
+        .nth(1)
+        .and_then(|seed| seed.parse().ok());
+    let mut game = match seed {
+        Some(seed) => GameOfLife::with_seed(game_width, game_height, seed),
+        None => GameOfLife::new(game_width, game_height),
+    };
+
+    // Paletas que se recorren con la tecla P. Con `--palette nombre` se empieza con una de
+    // las incluidas y con `--palette archivo` se agrega una paleta propia leída de un archivo.
+    let mut palettes = palette::builtin();
+    let mut palette_index = 0;
+    if let Some(choice) = std::env::args().skip_while(|arg| arg != "--palette").nth(1) {
+        match palettes.iter().position(|palette| palette.name.eq_ignore_ascii_case(&choice)) {
+            Some(index) => palette_index = index,
+            None => match Palette::load(&choice) {
+                Ok(palette) => {
+                    palettes.push(palette);
+                    palette_index = palettes.len() - 1;
+                }
+                Err(err) => println!("{}", err),
+            },
+        }
+    }
+    game.set_palette(palettes[palette_index].clone());
+    framebuffer.set_background_color(game.palette.background);
+    framebuffer.clear();
 
     // Configurar variación de color (similar al Python)
     game.set_color_variation(0.05);
 
+    // Variación de la saturación y del brillo (0.0 = colores puros, como los del matiz)
+    // game.set_saturation_variation(0.03);
+    // game.set_value_variation(0.03);
+
     // ========================================
     // AQUÍ PUEDES AGREGAR TUS PATRONES
     // ========================================
@@ -76,52 +143,281 @@ fn main() {
     // Ejemplo 6: Flores
     add_flowers(&mut game);
 
-    // Calcular offset para centrar el juego en el framebuffer
-    let offset_x = (framebuffer_width - (game_width * cell_scale)) / 2;
-    let offset_y = (framebuffer_height - (game_height * cell_scale)) / 2;
-
     let mut frame_count = 0;
-    let mut last_update = std::time::Instant::now();
-    let update_interval = Duration::from_millis(100); // 10 FPS para mejor visualización
+    let mut preset_index = 0;
+    let mut square_rule = game.rule.clone();
+    let hex_rule: Rule = "B2/S34H".parse().expect("regla hexagonal válida");
+    let mut kernels = vec![
+        ("regla".to_string(), None),
+        ("Moore r2".to_string(), Some(Kernel::moore(2))),
+        ("von Neumann r2".to_string(), Some(Kernel::von_neumann(2))),
+        ("cruz r2".to_string(), Some(Kernel::cross(2))),
+        ("salto de caballo".to_string(), Some(Kernel::knight())),
+    ];
+    // Con `--kernel archivo` se agrega un kernel propio escrito como cuadrícula de pesos
+    if let Some(path) = std::env::args().skip_while(|arg| arg != "--kernel").nth(1) {
+        match std::fs::read_to_string(&path) {
+            Ok(text) => match Kernel::from_grid(&text) {
+                Ok(kernel) => kernels.push((path, Some(kernel))),
+                Err(err) => println!("No se pudo leer el kernel {}: {}", path, err),
+            },
+            Err(err) => println!("No se pudo abrir el kernel {}: {}", path, err),
+        }
+    }
+    let mut kernel_index = 0;
+    let mut boundary_index = 0;
+    let mut infinite: Option<Scheduler<SparseLife>> = None; // Plano infinito (tecla I)
+    let mut view = (0i64, 0i64); // Célula en la esquina superior izquierda del plano infinito
+    let mut last_camera = None; // Posición del grid en el último frame dibujado
+    let mut speed_index = 0;
+    let inheritances = inheritance::builtin();
+    let mut inheritance_index = 0;
 
     println!("=== Juego de la Vida de Conway ===");
-    println!("Grid: {}x{} células", game_width, game_height);
+    println!("Grid: {}x{} células ({} hilos)", game_width, game_height, game.threads);
+    println!("Regla: {}", game.rule);
+    println!("Semilla: {} (repetir con cargo run -- --seed {})", game.seed(), game.seed());
     println!("Modifica el código para cambiar los patrones!");
+    println!("Presiona R para cambiar de regla y M para exportarla en formato MAP");
+    println!("Presiona K para cambiar la vecindad (kernel, o uno propio con --kernel archivo)");
+    println!("Presiona B para cambiar la frontera (muerta, toroide, Klein, cruce, espejo)");
+    println!("Presiona E para cambiar el motor (célula por célula, paralelo por bits, bloques con tabla)");
+    println!("Presiona J para saltar 1024 generaciones con HashLife (sin colores)");
+    println!("Presiona I para pasar al plano infinito (flechas para moverse) y volver");
+    println!("Presiona H para reiniciar la misma semilla en la red hexagonal o cuadrada");
+    println!("Presiona C para cambiar la herencia de color (promedio, mayoría, azar, más viejo, mezclas)");
+    println!("Presiona A para cambiar el modo de color (heredado, matiz, edad, edad oscurecida, estado)");
+    println!("Presiona P para cambiar la paleta (paleta actual: {})", game.palette.name);
+    println!("Presiona V para cambiar la velocidad (10 o 60 gen/s, sin límite, 1 por frame)");
     println!("================================");
 
+    // La simulación avanza en su propio hilo; el bucle solo dibuja
+    let mut simulation = Scheduler::new(game, SPEEDS[speed_index].1);
+
     while !window.window_should_close() {
-        let now = std::time::Instant::now();
+        {
+            // Las teclas modifican la simulación: se toma el candado mientras se atienden
+            let mut game = simulation.lock();
+
+            // Cambiar entre las reglas predefinidas
+            if window.is_key_pressed(KeyboardKey::KEY_R) {
+                preset_index = (preset_index + 1) % PRESETS.len();
+                let (name, rule) = PRESETS[preset_index];
+                match game.set_rule_str(rule) {
+                    Ok(()) => {
+                        if let Some(sparse) = &infinite {
+                            sparse.lock().set_rule(game.rule.clone());
+                        }
+                        println!("Regla cambiada a {} ({})", name, game.rule)
+                    }
+                    Err(err) => println!("No se pudo cambiar la regla: {}", err),
+                }
+            }
+
+            // Comparar la misma semilla en la red cuadrada y en la hexagonal
+            if window.is_key_pressed(KeyboardKey::KEY_H) {
+                if game.rule.is_hexagonal() {
+                    game.set_rule(square_rule.clone());
+                } else {
+                    square_rule = game.rule.clone();
+                    game.set_rule(hex_rule.clone());
+                }
+                add_flowers(&mut game);
+                println!("Semilla reiniciada con la regla {}", game.rule);
+            }
+
+            // Cambiar entre la vecindad de la regla y los kernels predefinidos
+            if window.is_key_pressed(KeyboardKey::KEY_K) {
+                kernel_index = (kernel_index + 1) % kernels.len();
+                let (name, kernel) = &kernels[kernel_index];
+                game.set_kernel(kernel.clone());
+                if let Some(sparse) = &infinite {
+                    sparse.lock().set_kernel(kernel.clone());
+                }
+                match kernel {
+                    Some(kernel) => println!("Vecindad: {} (suma máxima {})", name, kernel.max_sum()),
+                    None => println!("Vecindad: {}", name),
+                }
+            }
 
-        // Actualizar el juego cada cierto intervalo
-        if now.duration_since(last_update) >= update_interval {
-            game.update();
-            last_update = now;
+            // Cambiar la condición de frontera
+            if window.is_key_pressed(KeyboardKey::KEY_B) {
+                boundary_index = (boundary_index + 1) % Boundary::ALL.len();
+                game.set_boundary(Boundary::ALL[boundary_index]);
+                println!("Frontera: {}", game.boundary);
+            }
+
+            // Cambiar el motor de la simulación (el resultado es el mismo)
+            if window.is_key_pressed(KeyboardKey::KEY_E) {
+                let engine = match game.engine {
+                    Engine::PerCell => Engine::BitParallel,
+                    Engine::BitParallel => Engine::Lookup,
+                    Engine::Lookup => Engine::PerCell,
+                };
+                game.set_engine(engine);
+                println!("Motor: {:?}", engine);
+            }
+
+            // Cambiar la forma en que las células que nacen heredan el color
+            if window.is_key_pressed(KeyboardKey::KEY_C) {
+                inheritance_index = (inheritance_index + 1) % inheritances.len();
+                game.set_color_inheritance(inheritances[inheritance_index].clone());
+                if let Some(sparse) = &infinite {
+                    sparse.lock().set_color_inheritance(game.inheritance.clone());
+                }
+                println!("Herencia de color: {}", game.inheritance.name());
+            }
+
+            // Cambiar lo que representa el color de cada célula
+            if window.is_key_pressed(KeyboardKey::KEY_A) {
+                let mode = match game.color_mode {
+                    ColorMode::Inherited => ColorMode::Hue,
+                    ColorMode::Hue => ColorMode::Age,
+                    ColorMode::Age => ColorMode::AgeShaded,
+                    ColorMode::AgeShaded => ColorMode::State,
+                    ColorMode::State => ColorMode::Inherited,
+                };
+                game.set_color_mode(mode);
+                if let Some(sparse) = &infinite {
+                    sparse.lock().set_color_mode(mode);
+                }
+                println!("Modo de color: {:?}", mode);
+            }
+
+            // Cambiar la paleta: el fondo cambia, así que se limpia el framebuffer y se redibuja todo
+            if window.is_key_pressed(KeyboardKey::KEY_P) {
+                palette_index = (palette_index + 1) % palettes.len();
+                let palette = palettes[palette_index].clone();
+                framebuffer.set_background_color(palette.background);
+                if let Some(sparse) = &infinite {
+                    sparse.lock().palette = palette.clone();
+                }
+                println!("Paleta: {}", palette.name);
+                game.set_palette(palette);
+                last_camera = None;
+            }
+
+            // Saltar 2^10 generaciones de golpe con HashLife
+            if window.is_key_pressed(KeyboardKey::KEY_J) {
+                match HashLife::from_game(&game) {
+                    Some(mut life) => {
+                        life.step(10);
+                        life.write_to_game(&mut game);
+                        println!(
+                            "HashLife: +1024 generaciones (generación {}), {} células vivas, {} nodos",
+                            life.generation,
+                            life.population(),
+                            life.node_count()
+                        );
+                    }
+                    None => println!("HashLife no disponible: necesita bordes muertos y una regla de dos estados de la vecindad 3x3, sin B0 ni kernel"),
+                }
+            }
+
+            // Exportar la regla actual para usarla en Golly o LifeViewer
+            if window.is_key_pressed(KeyboardKey::KEY_M) {
+                match game.rule.to_map_string() {
+                    Some(map) => println!("Regla {} en formato MAP: {}", game.rule, map),
+                    None => println!("La regla {} no usa la vecindad 3x3 y no tiene formato MAP", game.rule),
+                }
+            }
+        }
+
+        // Pasar el grid actual al plano infinito o volver al grid acotado. El grid acotado
+        // queda en pausa mientras se usa el plano infinito.
+        if window.is_key_pressed(KeyboardKey::KEY_I) {
+            if let Some(sparse) = infinite.take() {
+                simulation.set_speed(sparse.speed());
+                println!("De vuelta al grid acotado");
+            } else {
+                let speed = simulation.speed();
+                simulation.set_speed(Speed::PerFrame(0));
+                infinite = Some(Scheduler::new(SparseLife::from_game(&simulation.lock()), speed));
+                view = (0, 0);
+                println!("Plano infinito: usa las flechas para moverte");
+            }
+        }
+
+        // Mover la vista del plano infinito
+        if infinite.is_some() {
+            let step = 2;
+            if window.is_key_down(KeyboardKey::KEY_LEFT) { view.0 -= step; }
+            if window.is_key_down(KeyboardKey::KEY_RIGHT) { view.0 += step; }
+            if window.is_key_down(KeyboardKey::KEY_UP) { view.1 -= step; }
+            if window.is_key_down(KeyboardKey::KEY_DOWN) { view.1 += step; }
         }
 
-        // Limpiar el framebuffer
-        framebuffer.clear();
+        // Cambiar la velocidad de la simulación
+        if window.is_key_pressed(KeyboardKey::KEY_V) {
+            speed_index = (speed_index + 1) % SPEEDS.len();
+            let (name, speed) = SPEEDS[speed_index];
+            match &infinite {
+                Some(sparse) => sparse.set_speed(speed),
+                None => simulation.set_speed(speed),
+            }
+            println!("Velocidad: {}", name);
+        }
 
-        // Renderizar el juego
-        game.render(&mut framebuffer, offset_x, offset_y, cell_scale);
+        // Renderizar el juego centrado en el framebuffer (la red hexagonal ocupa otro tamaño)
+        let (render_width, render_height) = simulation.lock().render_size(cell_scale);
+        let offset_x = framebuffer_width.saturating_sub(render_width) / 2;
+        let offset_y = framebuffer_height.saturating_sub(render_height) / 2;
+        let camera = (offset_x, offset_y, infinite.is_some());
+        match &mut infinite {
+            Some(sparse) => {
+                framebuffer.clear();
+                sparse.snapshot().render(
+                    &mut framebuffer,
+                    view,
+                    (game_width, game_height),
+                    (offset_x, offset_y),
+                    cell_scale,
+                );
+            }
+            None => {
+                // Si la cámara cambió se limpia el framebuffer y se redibuja todo; si no,
+                // solo las células que cambiaron desde el frame anterior
+                if last_camera != Some(camera) {
+                    framebuffer.clear();
+                    simulation.lock().invalidate_render();
+                }
+                simulation
+                    .snapshot()
+                    .render_changes(&mut framebuffer, offset_x, offset_y, cell_scale);
+            }
+        }
+        last_camera = Some(camera);
 
         // Mostrar estadísticas en la consola cada 100 frames
         if frame_count % 100 == 0 {
-            let (alive, total) = game.get_stats();
-            println!(
-                "Frame {}: {} células vivas de {} totales ({:.1}%)",
-                frame_count,
-                alive,
-                total,
-                (alive as f32 / total as f32) * 100.0
-            );
+            if let Some(sparse) = &infinite {
+                let sparse = sparse.lock();
+                let (alive, chunks) = sparse.get_stats();
+                println!(
+                    "Frame {}: {} células vivas en {} bloques (generación {})",
+                    frame_count,
+                    alive,
+                    chunks,
+                    sparse.generation
+                );
+            } else {
+                let game = simulation.lock();
+                let (alive, total, active_tiles) = game.get_stats();
+                println!(
+                    "Frame {}: {} células vivas de {} totales ({:.1}%), {} de {} bloques activos",
+                    frame_count,
+                    alive,
+                    total,
+                    (alive as f32 / total as f32) * 100.0,
+                    active_tiles,
+                    game.tile_count()
+                );
+            }
         }
 
-        // Intercambiar buffer y mostrar
+        // Intercambiar buffer y mostrar (raylib espera lo necesario para los 60 FPS)
         framebuffer.swap_buffer(&mut window, &raylib_thread);
 
-        // Pequeño delay para controlar la velocidad
-        thread::sleep(Duration::from_millis(16)); // ~60 FPS para la ventana
-
         frame_count += 1;
     }
 }
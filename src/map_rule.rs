@@ -0,0 +1,123 @@
+use crate::rule::RuleParseError;
+
+// Reglas MAP de Golly/LifeViewer: la tabla completa de 512 transiciones de la vecindad 3x3
+// codificada en base64. El bit i (de mayor a menor dentro de cada byte) es el resultado
+// para la vecindad con índice i, donde NW vale 256, N 128, NE 64, W 32, C 16, E 8,
+// SW 4, S 2 y SE 1.
+
+const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+// 512 bits en base64 sin relleno
+pub const MAP_LENGTH: usize = 86;
+
+// Separar "MAP<base64>" de un sufijo opcional de estados ("/3" o "/C3")
+pub fn split(rule: &str) -> Option<(&str, Option<&str>)> {
+    let body = rule
+        .strip_prefix("MAP")
+        .or_else(|| rule.strip_prefix("map"))?;
+    let Some(rest) = body.get(MAP_LENGTH..) else {
+        return Some((body, None));
+    };
+
+    let rest = rest.trim_start_matches('=');
+    if rest.is_empty() {
+        return Some((&body[..MAP_LENGTH], None));
+    }
+    match rest.strip_prefix('/') {
+        Some(states) => Some((&body[..MAP_LENGTH], Some(states))),
+        None => Some((body, None)),
+    }
+}
+
+// Decodificar la tabla de transiciones
+pub fn parse(map: &str) -> Result<Box<[bool; 512]>, RuleParseError> {
+    let invalid = || RuleParseError::InvalidMap(map.to_string());
+    if map.len() != MAP_LENGTH {
+        return Err(invalid());
+    }
+
+    let mut table = Box::new([false; 512]);
+    let mut bit = 0;
+    for found in map.bytes() {
+        let value = ALPHABET
+            .iter()
+            .position(|&c| c == found)
+            .ok_or_else(invalid)?;
+        for shift in (0..6).rev() {
+            if bit < 512 {
+                table[bit] = (value >> shift) & 1 == 1;
+            }
+            bit += 1;
+        }
+    }
+    Ok(table)
+}
+
+// Codificar una tabla de transiciones como "MAP<base64>"
+pub fn format(table: &[bool; 512]) -> String {
+    let mut text = String::with_capacity(3 + MAP_LENGTH);
+    text.push_str("MAP");
+    for chunk in 0..MAP_LENGTH {
+        let mut value = 0;
+        for offset in 0..6 {
+            let bit = chunk * 6 + offset;
+            value = (value << 1) | (bit < 512 && table[bit]) as usize;
+        }
+        text.push(ALPHABET[value] as char);
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rule::Rule;
+
+    // Regla de Conway en formato MAP, tal como la exporta Golly
+    const CONWAY: &str =
+        "MAPARYXfhZofugWaH7oaIDogBZofuhogOiAaIDogIAAgAAWaH7oaIDogGiA6ICAAIAAaIDogIAAgACAAIAAAAAAAA";
+
+    #[test]
+    fn conway_map_matches_rulestring() {
+        let map: Rule = CONWAY.parse().unwrap();
+        let conway = Rule::conway();
+        assert_eq!(map.transition_table(), conway.transition_table());
+        assert_eq!(map.to_string(), "B3/S23");
+        assert_eq!(conway.to_map_string().as_deref(), Some(CONWAY));
+    }
+
+    #[test]
+    fn format_round_trips() {
+        let mut table = [false; 512];
+        for (index, cell) in table.iter_mut().enumerate() {
+            *cell = (index * 7919) % 5 < 2;
+        }
+        let text = format(&table);
+        let (map, states) = split(&text).unwrap();
+        assert_eq!(states, None);
+        assert_eq!(*parse(map).unwrap(), table);
+
+        // Las tablas anisotrópicas se escriben en formato MAP
+        let rule: Rule = text.parse().unwrap();
+        assert_eq!(rule.to_string(), text);
+    }
+
+    #[test]
+    fn states_and_padding() {
+        let body = &CONWAY[3..];
+        assert_eq!(split(&format!("MAP{body}/3")), Some((body, Some("3"))));
+        assert_eq!(split(&format!("MAP{body}==")), Some((body, None)));
+        let rule: Rule = format!("MAP{body}/C3").parse().unwrap();
+        assert_eq!(rule.states, 3);
+        assert_eq!(rule.to_map_string(), Some(format!("MAP{body}/3")));
+        assert_eq!(rule.to_string(), "B3/S23/C3");
+    }
+
+    #[test]
+    fn invalid_maps() {
+        assert!(matches!(parse("ABC"), Err(RuleParseError::InvalidMap(_))));
+        let bad = format!("{}!", &CONWAY[3..CONWAY.len() - 1]);
+        assert!(matches!(parse(&bad), Err(RuleParseError::InvalidMap(_))));
+        assert!("MAPABC".parse::<Rule>().is_err());
+    }
+}
@@ -0,0 +1,625 @@
+use crate::game_of_life::{Hsv, Hue};
+use raylib::prelude::Color;
+use std::fmt;
+use std::path::Path;
+use std::str::FromStr;
+
+// Paletas de colores: un fondo, un color para las células vivas sin color y una lista de
+// colores (paradas) que se recorre para convertir un matiz, una edad o un estado en color.
+// Además de las paletas incluidas se pueden leer paletas propias de un archivo de texto
+// (o de JSON, con las mismas claves y las paradas en "colores"):
+//
+//     // Comentario
+//     nombre = atardecer
+//     fondo = #101018
+//     viva = #ffffff
+//     ciclica = no
+//     discreta = no
+//     #1b1b3a
+//     #693668
+//     #f7b267
+//
+// Cada línea sin clave es una parada (#rrggbb o rrggbb), en orden; las claves son opcionales.
+#[derive(Clone, Debug)]
+pub struct Palette {
+    pub name: String,
+    pub background: Color, // Color de las células muertas
+    pub alive: Color,      // Color de las células vivas que no tienen color
+    pub stops: Vec<Color>, // Paradas del degradado, al menos una
+    pub cyclic: bool,      // El último color vuelve al primero (para recorrer los matices)
+    pub discrete: bool,    // Colores separados, sin interpolar entre paradas
+}
+
+// Errores al leer una paleta de un archivo de texto (las líneas se cuentan desde 1)
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PaletteError {
+    NoColors,
+    InvalidColor { line: usize, found: String },
+    UnknownKey { line: usize, key: String },
+    InvalidFlag { line: usize, found: String },
+    InvalidJson { line: usize, expected: &'static str },
+    Io(String),
+}
+
+impl fmt::Display for PaletteError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PaletteError::NoColors => write!(f, "la paleta no tiene colores"),
+            PaletteError::InvalidColor { line, found } => write!(
+                f,
+                "color inválido '{found}' en la línea {line}: se esperaba #rrggbb"
+            ),
+            PaletteError::UnknownKey { line, key } => write!(
+                f,
+                "clave desconocida '{key}' en la línea {line}: se esperaba nombre, fondo, viva, ciclica, discreta o (en JSON) colores"
+            ),
+            PaletteError::InvalidFlag { line, found } => write!(
+                f,
+                "valor inválido '{found}' en la línea {line}: se esperaba si o no (true o false en JSON)"
+            ),
+            PaletteError::InvalidJson { line, expected } => {
+                write!(
+                    f,
+                    "JSON inválido en la línea {line}: se esperaba {expected}"
+                )
+            }
+            PaletteError::Io(err) => write!(f, "no se pudo leer la paleta: {err}"),
+        }
+    }
+}
+
+impl std::error::Error for PaletteError {}
+
+impl Palette {
+    // Paleta de degradado con fondo negro
+    fn gradient(name: &str, alive: Color, stops: &[(u8, u8, u8)]) -> Self {
+        Palette {
+            name: name.to_string(),
+            background: Color::BLACK,
+            alive,
+            stops: stops
+                .iter()
+                .map(|&(r, g, b)| Color::new(r, g, b, 255))
+                .collect(),
+            cyclic: false,
+            discrete: false,
+        }
+    }
+
+    // Degradado de edad original: de azul oscuro a amarillo claro
+    pub fn classic() -> Self {
+        Palette::gradient(
+            "clásica",
+            Color::WHITE,
+            &[
+                (30, 30, 110),
+                (120, 20, 140),
+                (210, 40, 40),
+                (255, 150, 30),
+                (255, 255, 170),
+            ],
+        )
+    }
+
+    // Círculo de matices completo: los colores heredados quedan igual que con Hsv::to_color
+    // (por defecto)
+    pub fn rainbow() -> Self {
+        Palette {
+            cyclic: true,
+            ..Palette::gradient(
+                "arcoíris",
+                Color::WHITE,
+                &[
+                    (255, 0, 0),
+                    (255, 255, 0),
+                    (0, 255, 0),
+                    (0, 255, 255),
+                    (0, 0, 255),
+                    (255, 0, 255),
+                ],
+            )
+        }
+    }
+
+    // Viridis: uniforme perceptualmente, de violeta oscuro a amarillo
+    pub fn viridis() -> Self {
+        Palette::gradient(
+            "viridis",
+            Color::new(253, 231, 37, 255),
+            &[
+                (68, 1, 84),
+                (71, 44, 122),
+                (59, 81, 139),
+                (44, 113, 142),
+                (33, 144, 141),
+                (39, 173, 129),
+                (92, 200, 99),
+                (170, 220, 50),
+                (253, 231, 37),
+            ],
+        )
+    }
+
+    // Magma: uniforme perceptualmente, de violeta casi negro a blanco amarillento
+    pub fn magma() -> Self {
+        Palette::gradient(
+            "magma",
+            Color::new(252, 253, 191, 255),
+            &[
+                (28, 16, 68),
+                (79, 18, 123),
+                (129, 37, 129),
+                (181, 54, 122),
+                (229, 80, 100),
+                (251, 135, 97),
+                (254, 194, 135),
+                (252, 253, 191),
+            ],
+        )
+    }
+
+    // Colores suaves que recorren los matices, sobre un fondo gris azulado
+    pub fn pastel() -> Self {
+        Palette {
+            background: Color::new(24, 24, 32, 255),
+            cyclic: true,
+            ..Palette::gradient(
+                "pastel",
+                Color::new(245, 245, 245, 255),
+                &[
+                    (255, 179, 186),
+                    (255, 223, 186),
+                    (255, 255, 186),
+                    (186, 255, 201),
+                    (186, 225, 255),
+                    (218, 198, 255),
+                ],
+            )
+        }
+    }
+
+    // Escala de grises
+    pub fn monochrome() -> Self {
+        Palette::gradient("monocromo", Color::WHITE, &[(60, 60, 60), (255, 255, 255)])
+    }
+
+    // Colores de Okabe-Ito, distinguibles con los tipos comunes de daltonismo. Son colores
+    // separados: los matices se agrupan en 7 colores en lugar de mezclarse.
+    pub fn colorblind() -> Self {
+        Palette {
+            cyclic: true,
+            discrete: true,
+            ..Palette::gradient(
+                "daltonismo",
+                Color::WHITE,
+                &[
+                    (230, 159, 0),
+                    (86, 180, 233),
+                    (0, 158, 115),
+                    (240, 228, 66),
+                    (0, 114, 178),
+                    (213, 94, 0),
+                    (204, 121, 167),
+                ],
+            )
+        }
+    }
+
+    // Leer una paleta de un archivo de texto o JSON (si empieza con '{'); si no tiene nombre
+    // toma el del archivo
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, PaletteError> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path)
+            .map_err(|err| PaletteError::Io(format!("{}: {err}", path.display())))?;
+        let mut palette = if text.trim_start().starts_with('{') {
+            Palette::from_json(&text)?
+        } else {
+            text.parse()?
+        };
+        if palette.name.is_empty() {
+            palette.name = path
+                .file_stem()
+                .map_or_else(String::new, |stem| stem.to_string_lossy().into_owned());
+        }
+        Ok(palette)
+    }
+
+    // Color en la posición `t` entre 0.0 (primera parada) y 1.0 (última parada)
+    pub fn sample(&self, t: f32) -> Color {
+        let t = t.clamp(0.0, 1.0);
+        let count = self.stops.len();
+        if self.discrete || count == 1 {
+            return self.stops[((t * count as f32) as usize).min(count - 1)];
+        }
+        let position = t * (count - 1) as f32;
+        let first = (position as usize).min(count - 2);
+        lerp(
+            self.stops[first],
+            self.stops[first + 1],
+            position - first as f32,
+        )
+    }
+
+    // Color de un matiz. En las paletas cíclicas el matiz recorre todas las paradas y entre
+    // la última y la primera también se interpola; en las demás es lo mismo que sample.
+    pub fn sample_hue(&self, hue: Hue) -> Color {
+        let count = self.stops.len();
+        if !self.cyclic || self.discrete || count == 1 {
+            return self.sample(hue.value);
+        }
+        let position = hue.value * count as f32;
+        let first = (position as usize).min(count - 1);
+        lerp(
+            self.stops[first],
+            self.stops[(first + 1) % count],
+            position - first as f32,
+        )
+    }
+
+    // Color de un HSV completo: el matiz pasado por la paleta, mezclado con blanco según la
+    // saturación y oscurecido según el brillo. Con la paleta arcoíris es el mismo color que
+    // Hsv::to_color.
+    pub fn sample_hsv(&self, hsv: Hsv) -> Color {
+        let color = lerp(Color::WHITE, self.sample_hue(hsv.hue), hsv.saturation);
+        lerp(Color::BLACK, color, hsv.value)
+    }
+
+    // Leer una paleta en JSON: un objeto con las claves del formato de texto (cadenas para
+    // los colores y el nombre, true/false para ciclica y discreta) y las paradas en la
+    // lista "colores"
+    //
+    //     { "nombre": "atardecer", "fondo": "#101018", "colores": ["#1b1b3a", "#f7b267"] }
+    pub fn from_json(text: &str) -> Result<Self, PaletteError> {
+        let mut palette = Palette {
+            name: String::new(),
+            stops: Vec::new(),
+            ..Palette::classic()
+        };
+        let mut reader = JsonReader { text, position: 0 };
+        reader.expect('{', "'{'")?;
+        if !reader.next_is('}') {
+            loop {
+                let (line, key) = reader.string()?;
+                reader.expect(':', "':'")?;
+                match key.to_lowercase().as_str() {
+                    "nombre" => palette.name = reader.string()?.1,
+                    "fondo" => palette.background = reader.color()?,
+                    "viva" => palette.alive = reader.color()?,
+                    "ciclica" | "cíclica" => palette.cyclic = reader.flag()?,
+                    "discreta" => palette.discrete = reader.flag()?,
+                    "colores" => {
+                        reader.expect('[', "una lista de colores")?;
+                        if !reader.next_is(']') {
+                            loop {
+                                palette.stops.push(reader.color()?);
+                                if !reader.next_is(',') {
+                                    reader.expect(']', "',' o ']'")?;
+                                    break;
+                                }
+                            }
+                        }
+                    }
+                    _ => return Err(PaletteError::UnknownKey { line, key }),
+                }
+                if !reader.next_is(',') {
+                    reader.expect('}', "',' o '}'")?;
+                    break;
+                }
+            }
+        }
+        reader.skip_whitespace();
+        if reader.position < text.len() {
+            return Err(reader.error("el final del archivo"));
+        }
+        if palette.stops.is_empty() {
+            return Err(PaletteError::NoColors);
+        }
+        Ok(palette)
+    }
+}
+
+impl Default for Palette {
+    fn default() -> Self {
+        Palette::rainbow()
+    }
+}
+
+impl FromStr for Palette {
+    type Err = PaletteError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut palette = Palette {
+            name: String::new(),
+            stops: Vec::new(),
+            ..Palette::classic()
+        };
+        for (number, line) in s.lines().enumerate() {
+            let line_number = number + 1;
+            let line = line.split("//").next().unwrap_or("").trim();
+            if line.is_empty() {
+                continue;
+            }
+            let Some((key, value)) = line.split_once('=') else {
+                palette.stops.push(parse_color(line, line_number)?);
+                continue;
+            };
+            let value = value.trim();
+            match key.trim().to_lowercase().as_str() {
+                "nombre" => palette.name = value.to_string(),
+                "fondo" => palette.background = parse_color(value, line_number)?,
+                "viva" => palette.alive = parse_color(value, line_number)?,
+                "ciclica" | "cíclica" => palette.cyclic = parse_flag(value, line_number)?,
+                "discreta" => palette.discrete = parse_flag(value, line_number)?,
+                key => {
+                    return Err(PaletteError::UnknownKey {
+                        line: line_number,
+                        key: key.to_string(),
+                    });
+                }
+            }
+        }
+        if palette.stops.is_empty() {
+            return Err(PaletteError::NoColors);
+        }
+        Ok(palette)
+    }
+}
+
+// Paletas incluidas, en el orden en que se recorren con la tecla P
+pub fn builtin() -> Vec<Palette> {
+    vec![
+        Palette::rainbow(),
+        Palette::classic(),
+        Palette::viridis(),
+        Palette::magma(),
+        Palette::pastel(),
+        Palette::monochrome(),
+        Palette::colorblind(),
+    ]
+}
+
+// Interpolar entre dos colores (mix = 0.0 da `from`, 1.0 da `to`)
+fn lerp(from: Color, to: Color, mix: f32) -> Color {
+    let channel = |a: u8, b: u8| (a as f32 + (b as f32 - a as f32) * mix).round() as u8;
+    Color::new(
+        channel(from.r, to.r),
+        channel(from.g, to.g),
+        channel(from.b, to.b),
+        255,
+    )
+}
+
+// Leer un color "#rrggbb" o "rrggbb"
+fn parse_color(text: &str, line: usize) -> Result<Color, PaletteError> {
+    let invalid = || PaletteError::InvalidColor {
+        line,
+        found: text.to_string(),
+    };
+    let hex = text.strip_prefix('#').unwrap_or(text);
+    if hex.len() != 6 || !hex.is_ascii() {
+        return Err(invalid());
+    }
+    let channel =
+        |start: usize| u8::from_str_radix(&hex[start..start + 2], 16).map_err(|_| invalid());
+    Ok(Color::new(channel(0)?, channel(2)?, channel(4)?, 255))
+}
+
+// Leer un valor si/no
+fn parse_flag(text: &str, line: usize) -> Result<bool, PaletteError> {
+    match text.to_lowercase().as_str() {
+        "si" | "sí" | "true" => Ok(true),
+        "no" | "false" => Ok(false),
+        _ => Err(PaletteError::InvalidFlag {
+            line,
+            found: text.to_string(),
+        }),
+    }
+}
+
+// Lector de lo poco de JSON que usan las paletas: objetos, listas, cadenas y true/false
+struct JsonReader<'a> {
+    text: &'a str,
+    position: usize, // Byte del próximo carácter sin leer
+}
+
+impl JsonReader<'_> {
+    // Línea (desde 1) del próximo carácter sin leer
+    fn line(&self) -> usize {
+        self.text[..self.position].matches('\n').count() + 1
+    }
+
+    fn error(&self, expected: &'static str) -> PaletteError {
+        PaletteError::InvalidJson {
+            line: self.line(),
+            expected,
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        let rest = &self.text[self.position..];
+        self.position += rest.len() - rest.trim_start().len();
+    }
+
+    fn peek(&mut self) -> Option<char> {
+        self.skip_whitespace();
+        self.text[self.position..].chars().next()
+    }
+
+    // Consumir `symbol` si es el próximo carácter
+    fn next_is(&mut self, symbol: char) -> bool {
+        let found = self.peek() == Some(symbol);
+        if found {
+            self.position += symbol.len_utf8();
+        }
+        found
+    }
+
+    fn expect(&mut self, symbol: char, expected: &'static str) -> Result<(), PaletteError> {
+        if self.next_is(symbol) {
+            Ok(())
+        } else {
+            Err(self.error(expected))
+        }
+    }
+
+    // Leer una cadena entre comillas: (línea donde empieza, contenido sin escapes)
+    fn string(&mut self) -> Result<(usize, String), PaletteError> {
+        self.expect('"', "una cadena entre comillas")?;
+        let line = self.line();
+        let mut value = String::new();
+        let mut chars = self.text[self.position..].char_indices();
+        while let Some((offset, c)) = chars.next() {
+            match c {
+                '"' => {
+                    self.position += offset + 1;
+                    return Ok((line, value));
+                }
+                '\\' => {
+                    let escaped = match chars.next().map(|(_, c)| c) {
+                        Some('n') => '\n',
+                        Some('t') => '\t',
+                        Some('u') => {
+                            let digits: String = chars.by_ref().take(4).map(|(_, c)| c).collect();
+                            u32::from_str_radix(&digits, 16)
+                                .ok()
+                                .and_then(char::from_u32)
+                                .ok_or_else(|| self.error("un escape \\uXXXX válido"))?
+                        }
+                        Some(c @ ('"' | '\\' | '/')) => c,
+                        _ => return Err(self.error("un escape válido")),
+                    };
+                    value.push(escaped);
+                }
+                c => value.push(c),
+            }
+        }
+        self.position = self.text.len();
+        Err(self.error("'\"' al final de la cadena"))
+    }
+
+    fn color(&mut self) -> Result<Color, PaletteError> {
+        let (line, value) = self.string()?;
+        parse_color(&value, line)
+    }
+
+    fn flag(&mut self) -> Result<bool, PaletteError> {
+        self.skip_whitespace();
+        let line = self.line();
+        let rest = &self.text[self.position..];
+        let word = &rest[..rest
+            .find(|c: char| !c.is_alphanumeric())
+            .unwrap_or(rest.len())];
+        let value = match word {
+            "true" => true,
+            "false" => false,
+            _ => {
+                return Err(PaletteError::InvalidFlag {
+                    line,
+                    found: word.to_string(),
+                });
+            }
+        };
+        self.position += word.len();
+        Ok(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rgb(color: Color) -> (u8, u8, u8) {
+        (color.r, color.g, color.b)
+    }
+
+    #[test]
+    fn json_matches_text() {
+        let text: Palette = "nombre = atardecer\nfondo = #101018\nviva = #ffffff\nciclica = si\n#1b1b3a\n#693668\n#f7b267"
+            .parse()
+            .unwrap();
+        let json = Palette::from_json(
+            r##"{
+                "nombre": "atardecer",
+                "fondo": "#101018",
+                "viva": "#ffffff",
+                "ciclica": true,
+                "discreta": false,
+                "colores": ["#1b1b3a", "693668", "#f7b267"]
+            }"##,
+        )
+        .unwrap();
+        assert_eq!(json.name, text.name);
+        assert_eq!(rgb(json.background), rgb(text.background));
+        assert_eq!(rgb(json.alive), rgb(text.alive));
+        assert_eq!((json.cyclic, json.discrete), (text.cyclic, text.discrete));
+        let stops = |palette: &Palette| {
+            palette
+                .stops
+                .iter()
+                .map(|&color| rgb(color))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(stops(&json), stops(&text));
+    }
+
+    #[test]
+    fn invalid_json() {
+        let error = |text: &str| Palette::from_json(text).unwrap_err();
+        assert_eq!(error(r##"{ "colores": [] }"##), PaletteError::NoColors);
+        assert_eq!(
+            error("{\n  \"colores\": [\"#12345\"]\n}"),
+            PaletteError::InvalidColor {
+                line: 2,
+                found: "#12345".to_string()
+            }
+        );
+        assert_eq!(
+            error(r##"{ "brillo": "#ffffff" }"##),
+            PaletteError::UnknownKey {
+                line: 1,
+                key: "brillo".to_string()
+            }
+        );
+        assert_eq!(
+            error(r##"{ "ciclica": si, "colores": ["#ffffff"] }"##),
+            PaletteError::InvalidFlag {
+                line: 1,
+                found: "si".to_string()
+            }
+        );
+        assert_eq!(
+            error("{ \"colores\": [\"#ffffff\"]\n\n"),
+            PaletteError::InvalidJson {
+                line: 3,
+                expected: "',' o '}'"
+            }
+        );
+        assert_eq!(
+            error(r##"{ "colores": ["#ffffff"] } extra"##),
+            PaletteError::InvalidJson {
+                line: 1,
+                expected: "el final del archivo"
+            }
+        );
+    }
+
+    #[test]
+    fn rainbow_matches_hsv() {
+        let palette = Palette::rainbow();
+        for step in 0..60 {
+            let hsv = Hsv::new(
+                Hue::new(step as f32 / 60.0),
+                0.3 + (step % 7) as f32 / 10.0,
+                0.2 + (step % 5) as f32 / 5.0,
+            );
+            let (expected, sampled) = (hsv.to_color(), palette.sample_hsv(hsv));
+            for (a, b) in [
+                (expected.r, sampled.r),
+                (expected.g, sampled.g),
+                (expected.b, sampled.b),
+            ] {
+                assert!(a.abs_diff(b) <= 2, "{hsv:?}: {expected:?} {sampled:?}");
+            }
+        }
+    }
+}
@@ -0,0 +1,58 @@
+// Números pseudoaleatorios con semilla: con la misma semilla la simulación se repite
+// exactamente. Los cálculos de cada generación se reparten entre hilos, así que dentro de
+// `update` no se usa un generador con estado: cada célula deriva sus números de la
+// semilla, la generación y su posición (cell_random), sin importar el orden ni el hilo.
+
+// Flujos independientes de cell_random, uno por cada uso
+pub const STREAM_HUE: u64 = 1; // Matiz de las células que nacen sin padres con color
+pub const STREAM_VARIATION: u64 = 2; // Variación del matiz heredado
+pub const STREAM_PARENT: u64 = 3; // Semilla de las estrategias de herencia de color
+pub const STREAM_SET_CELL: u64 = 4; // Matiz de las células puestas con set_cell
+pub const STREAM_SATURATION: u64 = 5; // Variación de la saturación heredada
+pub const STREAM_VALUE: u64 = 6; // Variación del brillo heredado
+
+// Generador splitmix64: rápido, con estado de 64 bits y buena distribución
+#[derive(Clone, Debug)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Rng { state: seed }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        mix(self.state)
+    }
+}
+
+// Función de mezcla final de splitmix64
+fn mix(mut z: u64) -> u64 {
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+// Número pseudoaleatorio de la célula (x, y) en una generación, para el flujo `stream`
+pub fn cell_random(seed: u64, generation: u64, x: i64, y: i64, stream: u64) -> u64 {
+    [generation, x as u64, y as u64, stream]
+        .into_iter()
+        .fold(mix(seed), |hash, value| mix(hash ^ value.wrapping_mul(0x9E37_79B9_7F4A_7C15)))
+}
+
+// Pasar un número pseudoaleatorio a [0, 1) (usa los 24 bits altos, los que caben en un f32)
+pub fn unit(value: u64) -> f32 {
+    (value >> 40) as f32 / (1u64 << 24) as f32
+}
+
+// Semilla tomada del reloj, para cuando no se elige una
+pub fn time_seed() -> u64 {
+    use std::time::SystemTime;
+
+    let time = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_nanos() as u64);
+    mix(time)
+}
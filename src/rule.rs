@@ -0,0 +1,851 @@
+use crate::hensel;
+use crate::map_rule;
+use std::fmt;
+use std::str::FromStr;
+
+// Reglas conocidas que se pueden seleccionar por nombre
+pub const PRESETS: [(&str, &str); 12] = [
+    ("Conway", "B3/S23"),
+    ("HighLife", "B36/S23"),
+    ("Day & Night", "B3678/S34678"),
+    ("Seeds", "B2/S"),
+    ("Life without Death", "B3/S012345678"),
+    ("Maze", "B3/S12345"),
+    ("Brian's Brain", "B2/S/C3"),
+    ("Star Wars", "B2/S345/C4"),
+    ("Bosco", "R5,C0,M1,S34..58,B34..45,NM"),
+    ("Majority", "R4,C0,M1,S41..81,B41..81,NM"),
+    ("tlife", "B3/S2-i34q"),
+    ("Hex B2/S34", "B2/S34H"),
+];
+
+// Radio máximo admitido para las reglas Larger than Life
+pub const MAX_RANGE: u8 = 50;
+
+// Conteo máximo que se puede escribir en una sección B/S: el de la vecindad más grande
+// de Larger than Life (los kernels con pesos pueden superar los 8 vecinos)
+pub const MAX_COUNT: u32 = (2 * MAX_RANGE as u32 + 1) * (2 * MAX_RANGE as u32 + 1);
+
+// Forma de la vecindad usada para contar vecinos
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Neighborhood {
+    Moore,      // Cuadrado de lado 2r+1
+    VonNeumann, // Rombo: |dx| + |dy| <= r
+    Hexagonal,  // Red hexagonal de 6 vecinos (filas impares desplazadas media célula)
+}
+
+// Regla totalística externa: decide nacimiento y supervivencia según el número de vecinos vivos
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Rule {
+    pub birth: Vec<bool>,    // birth[n] = una célula muerta con n vecinos nace
+    pub survival: Vec<bool>, // survival[n] = una célula viva con n vecinos sobrevive
+    pub states: u8,          // Número de estados (2 = vida clásica, >2 = Generations)
+    pub range: u8,           // Radio de la vecindad (1 = vecindad clásica 3x3)
+    pub neighborhood: Neighborhood,
+    pub include_center: bool, // La propia célula cuenta como vecina (M1 en Larger than Life)
+    pub table: Option<Box<[bool; 512]>>, // Tabla de la vecindad 3x3 para reglas no totalísticas
+}
+
+// Errores al interpretar una regla en notación B/S
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum RuleParseError {
+    Empty,
+    MissingSeparator(String),
+    TooManySections(String),
+    InvalidCharacter {
+        section: char,
+        found: char,
+    },
+    DuplicateSection(char),
+    MixedNotation(String),
+    InvalidStates(String),
+    InvalidLargerThanLife(String),
+    InvalidRange(String),
+    CountOutOfRange {
+        section: char,
+        count: u32,
+        max: u32,
+    },
+    InvalidHensel {
+        section: char,
+        count: u8,
+        letter: char,
+    },
+    InvalidMap(String),
+    HexagonalTable(String),
+    InvalidCount {
+        section: char,
+        found: String,
+    },
+}
+
+impl fmt::Display for RuleParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RuleParseError::Empty => write!(f, "la regla está vacía"),
+            RuleParseError::MissingSeparator(rule) => {
+                write!(f, "falta el separador '/' en la regla \"{rule}\"")
+            }
+            RuleParseError::TooManySections(rule) => {
+                write!(f, "la regla \"{rule}\" tiene demasiadas secciones")
+            }
+            RuleParseError::InvalidCharacter { section, found } => write!(
+                f,
+                "carácter inválido '{found}' en la sección {section}: se esperaban dígitos del 0 al 8"
+            ),
+            RuleParseError::DuplicateSection(section) => {
+                write!(f, "la sección {section} aparece más de una vez")
+            }
+            RuleParseError::MixedNotation(rule) => write!(
+                f,
+                "la regla \"{rule}\" mezcla la notación B/S con la notación S/B sin letras"
+            ),
+            RuleParseError::InvalidStates(states) => write!(
+                f,
+                "número de estados inválido \"{states}\": se esperaba un entero entre 2 y 255"
+            ),
+            RuleParseError::InvalidLargerThanLife(item) => write!(
+                f,
+                "elemento inválido \"{item}\" en la regla Larger than Life: se esperaba R, C, M, S, B o N"
+            ),
+            RuleParseError::InvalidRange(range) => write!(
+                f,
+                "radio inválido \"{range}\": se esperaba un entero entre 1 y {MAX_RANGE}"
+            ),
+            RuleParseError::CountOutOfRange {
+                section,
+                count,
+                max,
+            } => write!(
+                f,
+                "la sección {section} usa {count} vecinos pero la vecindad solo tiene {max}"
+            ),
+            RuleParseError::InvalidHensel {
+                section,
+                count,
+                letter,
+            } => write!(
+                f,
+                "la letra '{letter}' no es válida para {count} vecinos en la sección {section} (válidas: \"{}\")",
+                hensel::letters(*count as usize)
+            ),
+            RuleParseError::InvalidMap(map) => write!(
+                f,
+                "tabla MAP inválida \"{map}\": se esperaban {} caracteres base64",
+                map_rule::MAP_LENGTH
+            ),
+            RuleParseError::HexagonalTable(rule) => write!(
+                f,
+                "la regla hexagonal \"{rule}\" solo admite conteos de vecinos (sin letras de Hensel)"
+            ),
+            RuleParseError::InvalidCount { section, found } => write!(
+                f,
+                "conteo inválido \"{found}\" en la sección {section}: se esperaban números o intervalos (\"10..12\") separados por comas"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for RuleParseError {}
+
+impl Rule {
+    // Regla clásica de Conway (B3/S23)
+    pub fn conway() -> Self {
+        Rule {
+            birth: Self::counts(&[3]),
+            survival: Self::counts(&[2, 3]),
+            states: 2,
+            range: 1,
+            neighborhood: Neighborhood::Moore,
+            include_center: false,
+            table: None,
+        }
+    }
+
+    // Buscar una regla predefinida por nombre (sin distinguir mayúsculas)
+    pub fn preset(name: &str) -> Option<Self> {
+        PRESETS
+            .iter()
+            .find(|(preset, _)| preset.eq_ignore_ascii_case(name))
+            .and_then(|(_, rule)| rule.parse().ok())
+    }
+
+    // Decidir si la célula estará viva en la siguiente generación
+    pub fn next_state(&self, is_alive: bool, neighbors: u32) -> bool {
+        let counts = if is_alive {
+            &self.survival
+        } else {
+            &self.birth
+        };
+        counts.get(neighbors as usize).copied().unwrap_or(false)
+    }
+
+    // Decidir el siguiente estado a partir del índice de 9 bits de la vecindad 3x3
+    // (solo para reglas con tabla; NW es el bit más alto y SE el más bajo)
+    pub fn next_state_from_index(&self, index: usize) -> bool {
+        match &self.table {
+            Some(table) => table[index],
+            None => {
+                let is_alive = index & hensel::CENTER != 0;
+                let neighbors = (index & !hensel::CENTER).count_ones();
+                self.next_state(is_alive, neighbors)
+            }
+        }
+    }
+
+    // Tabla completa de 512 transiciones (None si la vecindad no es la 3x3 clásica)
+    pub fn transition_table(&self) -> Option<Box<[bool; 512]>> {
+        if let Some(table) = &self.table {
+            return Some(table.clone());
+        }
+        self.is_totalistic_moore().then(|| {
+            Box::new(std::array::from_fn(|index| {
+                self.next_state_from_index(index)
+            }))
+        })
+    }
+
+    // Exportar la regla en formato MAP para compartirla con Golly o LifeViewer
+    pub fn to_map_string(&self) -> Option<String> {
+        let table = self.transition_table()?;
+        let mut map = map_rule::format(&table);
+        if self.is_generations() {
+            map.push_str(&format!("/{}", self.states));
+        }
+        Some(map)
+    }
+
+    // Número máximo de vecinos que puede contar la vecindad de la regla
+    pub fn max_neighbors(&self) -> u32 {
+        let r = self.range as u32;
+        let cells = match self.neighborhood {
+            Neighborhood::Moore => (2 * r + 1) * (2 * r + 1),
+            Neighborhood::VonNeumann => 2 * r * (r + 1) + 1,
+            Neighborhood::Hexagonal => 7,
+        };
+        if self.include_center {
+            cells
+        } else {
+            cells - 1
+        }
+    }
+
+    // Indica si la regla usa la red hexagonal (sufijo H: "B2/S34H")
+    pub fn is_hexagonal(&self) -> bool {
+        self.neighborhood == Neighborhood::Hexagonal
+    }
+
+    // Indica si la regla usa la vecindad clásica de 3x3 (notación B/S)
+    pub fn is_totalistic_moore(&self) -> bool {
+        self.range == 1 && self.neighborhood == Neighborhood::Moore && !self.include_center
+    }
+
+    // Indica si la regla tiene estados de decaimiento (reglas Generations)
+    pub fn is_generations(&self) -> bool {
+        self.states > 2
+    }
+
+    // Número de estados intermedios por los que pasa una célula antes de morir
+    pub fn dying_states(&self) -> u8 {
+        self.states.saturating_sub(2)
+    }
+
+    fn counts(values: &[u8]) -> Vec<bool> {
+        let mut counts = vec![false; 9];
+        for &value in values {
+            counts[value as usize] = true;
+        }
+        counts
+    }
+
+    // Conteos para los que al menos una disposición de la tabla nace o sobrevive
+    fn counts_from_table(table: &[bool; 512], alive: bool) -> Vec<bool> {
+        let mut counts = vec![false; 9];
+        for (index, &result) in table.iter().enumerate() {
+            if result && (index & hensel::CENTER != 0) == alive {
+                counts[(index & !hensel::CENTER).count_ones() as usize] = true;
+            }
+        }
+        counts
+    }
+
+    // Indica si una sección usa la notación de conteos grandes ("10..12,15") en lugar de dígitos
+    fn is_wide(digits: &str) -> bool {
+        digits.contains([',', '.'])
+    }
+
+    // Convertir los dígitos de una sección en la tabla de conteos
+    fn parse_counts(section: char, digits: &str) -> Result<Vec<bool>, RuleParseError> {
+        if Self::is_wide(digits) {
+            return Self::parse_wide_counts(section, digits);
+        }
+        let mut counts = vec![false; 9];
+        for found in digits.chars() {
+            match found.to_digit(10) {
+                Some(n) if n <= 8 => counts[n as usize] = true,
+                _ => return Err(RuleParseError::InvalidCharacter { section, found }),
+            }
+        }
+        Ok(counts)
+    }
+
+    // Convertir una sección de conteos grandes ("10..12,15") en la tabla de conteos
+    fn parse_wide_counts(section: char, digits: &str) -> Result<Vec<bool>, RuleParseError> {
+        let mut counts = vec![false; 9];
+        for item in digits.split(',').map(str::trim) {
+            let invalid = || RuleParseError::InvalidCount {
+                section,
+                found: item.to_string(),
+            };
+            let (min, max) = match item.split_once("..") {
+                Some((min, max)) => (min.parse::<u32>(), max.parse::<u32>()),
+                None => (item.parse::<u32>(), item.parse::<u32>()),
+            };
+            let (Ok(min), Ok(max)) = (min, max) else {
+                return Err(invalid());
+            };
+            if min > max {
+                return Err(invalid());
+            }
+            if max > MAX_COUNT {
+                return Err(RuleParseError::CountOutOfRange {
+                    section,
+                    count: max,
+                    max: MAX_COUNT,
+                });
+            }
+            if counts.len() <= max as usize {
+                counts.resize(max as usize + 1, false);
+            }
+            for count in min..=max {
+                counts[count as usize] = true;
+            }
+        }
+        Ok(counts)
+    }
+
+    // Interpretar la sección de estados ("C3", "G3" o simplemente "3")
+    fn parse_states(section: &str) -> Result<u8, RuleParseError> {
+        let digits = section.trim_start_matches(['C', 'c', 'G', 'g']);
+        match digits.parse::<u8>() {
+            Ok(states) if states >= 2 => Ok(states),
+            _ => Err(RuleParseError::InvalidStates(section.to_string())),
+        }
+    }
+
+    // Interpretar una regla Larger than Life como "R5,C0,M1,S34..58,B34..45,NM"
+    fn parse_larger_than_life(rule: &str) -> Result<Self, RuleParseError> {
+        let mut parsed = Rule {
+            birth: Vec::new(),
+            survival: Vec::new(),
+            states: 2,
+            range: 1,
+            neighborhood: Neighborhood::Moore,
+            include_center: false,
+            table: None,
+        };
+        let mut birth_ranges = Vec::new();
+        let mut survival_ranges = Vec::new();
+
+        for item in rule.split(',').map(str::trim) {
+            let invalid = || RuleParseError::InvalidLargerThanLife(item.to_string());
+            let mut chars = item.chars();
+            let letter = chars.next().ok_or_else(invalid)?.to_ascii_uppercase();
+            let value = chars.as_str();
+            match letter {
+                'R' => match value.parse::<u8>() {
+                    Ok(range) if (1..=MAX_RANGE).contains(&range) => parsed.range = range,
+                    _ => return Err(RuleParseError::InvalidRange(value.to_string())),
+                },
+                'C' => match value.parse::<u8>() {
+                    Ok(states) => parsed.states = states.max(2),
+                    Err(_) => return Err(RuleParseError::InvalidStates(value.to_string())),
+                },
+                'M' => match value {
+                    "0" => parsed.include_center = false,
+                    "1" => parsed.include_center = true,
+                    _ => return Err(invalid()),
+                },
+                'S' | 'B' => {
+                    let (min, max) = match value.split_once("..") {
+                        Some((min, max)) => (min.parse::<u32>(), max.parse::<u32>()),
+                        None => (value.parse::<u32>(), value.parse::<u32>()),
+                    };
+                    // Un intervalo invertido ("S9..3") no tiene ningún conteo: es un error
+                    let (Ok(min), Ok(max)) = (min, max) else {
+                        return Err(invalid());
+                    };
+                    if min > max {
+                        return Err(invalid());
+                    }
+                    let ranges = if letter == 'S' {
+                        &mut survival_ranges
+                    } else {
+                        &mut birth_ranges
+                    };
+                    ranges.push((letter, min, max));
+                }
+                'N' => match value.to_ascii_uppercase().as_str() {
+                    "M" => parsed.neighborhood = Neighborhood::Moore,
+                    "N" => parsed.neighborhood = Neighborhood::VonNeumann,
+                    _ => return Err(invalid()),
+                },
+                _ => return Err(invalid()),
+            }
+        }
+
+        // Construir las tablas de conteos ahora que se conoce el tamaño de la vecindad
+        let max = parsed.max_neighbors();
+        parsed.birth = vec![false; max as usize + 1];
+        parsed.survival = vec![false; max as usize + 1];
+        for (section, min, high) in birth_ranges.into_iter().chain(survival_ranges) {
+            if high > max {
+                return Err(RuleParseError::CountOutOfRange {
+                    section,
+                    count: high,
+                    max,
+                });
+            }
+            let counts = if section == 'S' {
+                &mut parsed.survival
+            } else {
+                &mut parsed.birth
+            };
+            for count in min..=high {
+                counts[count as usize] = true;
+            }
+        }
+
+        Ok(parsed)
+    }
+
+    // Escribir los conteos activos de una sección: dígitos ("23") si todos son de 0 a 8 y,
+    // si no, números e intervalos separados por comas ("10..12,15"). Un conteo grande solo
+    // se escribe como intervalo ("9..9") para no confundirlo con dígitos sueltos.
+    fn format_counts(counts: &[bool]) -> String {
+        let active: Vec<usize> = counts
+            .iter()
+            .enumerate()
+            .filter(|(_, enabled)| **enabled)
+            .map(|(n, _)| n)
+            .collect();
+        if active.iter().all(|&n| n <= 8) {
+            return active.iter().map(|&n| char::from(b'0' + n as u8)).collect();
+        }
+        let mut items = Vec::new();
+        let mut index = 0;
+        while index < active.len() {
+            let start = active[index];
+            while index + 1 < active.len() && active[index + 1] == active[index] + 1 {
+                index += 1;
+            }
+            let end = active[index];
+            items.push(if start == end {
+                start.to_string()
+            } else {
+                format!("{start}..{end}")
+            });
+            index += 1;
+        }
+        match items.as_slice() {
+            [single] if !single.contains('.') => format!("{single}..{single}"),
+            _ => items.join(","),
+        }
+    }
+
+    // Escribir los conteos activos como intervalos "S34..58,S60..62"
+    fn format_ranges(f: &mut fmt::Formatter<'_>, section: char, counts: &[bool]) -> fmt::Result {
+        let mut count = 0;
+        while count < counts.len() {
+            if !counts[count] {
+                count += 1;
+                continue;
+            }
+            let start = count;
+            while count + 1 < counts.len() && counts[count + 1] {
+                count += 1;
+            }
+            write!(f, ",{section}{start}..{count}")?;
+            count += 1;
+        }
+        Ok(())
+    }
+
+    // Las secciones con letras pueden usar la notación de Hensel ("B2-a/S12")
+    fn parse_lettered(sections: &[&str], states: u8) -> Result<Self, RuleParseError> {
+        // Con conteos grandes ("B10..12/S9..9") no hay letras de Hensel
+        if sections.iter().any(|section| Self::is_wide(section)) {
+            let mut rule = Rule {
+                birth: Vec::new(),
+                survival: Vec::new(),
+                states,
+                ..Rule::conway()
+            };
+            let mut seen = Vec::new();
+            for section in sections {
+                let (letter, digits) = section.split_at(1);
+                let letter = letter.to_ascii_uppercase().chars().next().unwrap_or('B');
+                if seen.contains(&letter) {
+                    return Err(RuleParseError::DuplicateSection(letter));
+                }
+                seen.push(letter);
+                let counts = Self::parse_counts(letter, digits)?;
+                if letter == 'B' {
+                    rule.birth = counts;
+                } else {
+                    rule.survival = counts;
+                }
+            }
+            return Ok(rule);
+        }
+
+        let mut birth = None;
+        let mut survival = None;
+        for section in sections {
+            let (letter, digits) = section.split_at(1);
+            let letter = letter.to_ascii_uppercase().chars().next().unwrap_or('B');
+            let slot = if letter == 'B' {
+                &mut birth
+            } else {
+                &mut survival
+            };
+            if slot.is_some() {
+                return Err(RuleParseError::DuplicateSection(letter));
+            }
+            *slot = Some(hensel::parse_section(letter, digits)?);
+        }
+        let birth: [u16; 9] = birth.unwrap_or([0; 9]);
+        let survival = survival.unwrap_or([0; 9]);
+
+        let table = (!hensel::is_totalistic(&birth) || !hensel::is_totalistic(&survival))
+            .then(|| hensel::build_table(&birth, &survival));
+        Ok(Rule {
+            birth: birth.iter().map(|&mask| mask != 0).collect(),
+            survival: survival.iter().map(|&mask| mask != 0).collect(),
+            states,
+            table,
+            ..Rule::conway()
+        })
+    }
+}
+
+impl Default for Rule {
+    fn default() -> Self {
+        Rule::conway()
+    }
+}
+
+impl FromStr for Rule {
+    type Err = RuleParseError;
+
+    // Acepta "B3/S23", "S23/B3" y la notación clásica "23/3" (supervivencia/nacimiento),
+    // con una tercera sección opcional de estados para Generations ("B2/S/C3" o "/2/3")
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let rule = s.trim();
+        if rule.is_empty() {
+            return Err(RuleParseError::Empty);
+        }
+
+        // Las reglas MAP traen la tabla completa en base64 (puede contener '/')
+        if let Some((map, states)) = map_rule::split(rule) {
+            let table = map_rule::parse(map)?;
+            return Ok(Rule {
+                birth: Self::counts_from_table(&table, false),
+                survival: Self::counts_from_table(&table, true),
+                states: states.map(Self::parse_states).transpose()?.unwrap_or(2),
+                table: Some(table),
+                ..Rule::conway()
+            });
+        }
+
+        // Las reglas Larger than Life empiezan por el radio: "R5,C0,M1,..."
+        if rule.starts_with(['R', 'r']) && rule.contains(',') {
+            return Self::parse_larger_than_life(rule);
+        }
+
+        let mut sections: Vec<&str> = rule.split('/').map(str::trim).collect();
+
+        // El sufijo H selecciona la red hexagonal ("B2/S34H" o "34/2H")
+        let mut hexagonal = false;
+        for section in sections.iter_mut() {
+            if let Some(stripped) = section.strip_suffix(['H', 'h']) {
+                *section = stripped;
+                hexagonal = true;
+            }
+        }
+
+        let states = match sections.len() {
+            1 => return Err(RuleParseError::MissingSeparator(rule.to_string())),
+            2 => 2,
+            3 => Self::parse_states(sections.pop().unwrap_or_default())?,
+            _ => return Err(RuleParseError::TooManySections(rule.to_string())),
+        };
+
+        let lettered = sections
+            .iter()
+            .filter(|section| section.starts_with(['B', 'b', 'S', 's']))
+            .count();
+
+        // Notación S/B sin letras: primero supervivencia, luego nacimiento
+        let mut parsed = if lettered == 0 {
+            Rule {
+                survival: Self::parse_counts('S', sections[0])?,
+                birth: Self::parse_counts('B', sections[1])?,
+                states,
+                ..Rule::conway()
+            }
+        } else if lettered == sections.len() {
+            Self::parse_lettered(&sections, states)?
+        } else {
+            return Err(RuleParseError::MixedNotation(rule.to_string()));
+        };
+
+        // Con conteos grandes las dos secciones tienen el mismo largo, para compararlas
+        let len = parsed.birth.len().max(parsed.survival.len()).max(9);
+        parsed.birth.resize(len, false);
+        parsed.survival.resize(len, false);
+
+        if hexagonal {
+            if parsed.table.is_some() {
+                return Err(RuleParseError::HexagonalTable(rule.to_string()));
+            }
+            for (section, counts) in [('B', &mut parsed.birth), ('S', &mut parsed.survival)] {
+                if let Some(count) = counts
+                    .iter()
+                    .rposition(|&enabled| enabled)
+                    .filter(|&count| count > 6)
+                {
+                    return Err(RuleParseError::CountOutOfRange {
+                        section,
+                        count: count as u32,
+                        max: 6,
+                    });
+                }
+                counts.truncate(7);
+            }
+            parsed.neighborhood = Neighborhood::Hexagonal;
+        }
+        Ok(parsed)
+    }
+}
+
+impl fmt::Display for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_hexagonal() {
+            write!(
+                f,
+                "B{}/S{}H",
+                Self::format_counts(&self.birth),
+                Self::format_counts(&self.survival)
+            )?;
+            if self.is_generations() {
+                write!(f, "/C{}", self.states)?;
+            }
+            return Ok(());
+        }
+
+        if let Some(table) = &self.table {
+            // Las tablas anisotrópicas no tienen notación de Hensel
+            if !hensel::is_isotropic(table) {
+                return write!(f, "{}", self.to_map_string().unwrap_or_default());
+            }
+            write!(
+                f,
+                "B{}/S{}",
+                hensel::format_section(table, false),
+                hensel::format_section(table, true)
+            )?;
+            if self.is_generations() {
+                write!(f, "/C{}", self.states)?;
+            }
+            return Ok(());
+        }
+
+        if !self.is_totalistic_moore() {
+            let states = if self.is_generations() {
+                self.states
+            } else {
+                0
+            };
+            write!(
+                f,
+                "R{},C{},M{}",
+                self.range, states, self.include_center as u8
+            )?;
+            Self::format_ranges(f, 'S', &self.survival)?;
+            Self::format_ranges(f, 'B', &self.birth)?;
+            let neighborhood = match self.neighborhood {
+                Neighborhood::Moore => 'M',
+                Neighborhood::VonNeumann => 'N',
+                Neighborhood::Hexagonal => 'H',
+            };
+            return write!(f, ",N{neighborhood}");
+        }
+
+        write!(
+            f,
+            "B{}/S{}",
+            Self::format_counts(&self.birth),
+            Self::format_counts(&self.survival)
+        )?;
+        if self.is_generations() {
+            write!(f, "/C{}", self.states)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(rule: &str) -> Rule {
+        rule.parse().unwrap_or_else(|err| panic!("{rule}: {err}"))
+    }
+
+    // Regla con conteos arbitrarios, con el mismo largo mínimo que deja el parser
+    fn totalistic(birth: &[u32], survival: &[u32]) -> Rule {
+        let max = birth
+            .iter()
+            .chain(survival)
+            .copied()
+            .max()
+            .unwrap_or(0)
+            .max(8) as usize;
+        let mut rule = Rule {
+            birth: vec![false; max + 1],
+            survival: vec![false; max + 1],
+            ..Rule::conway()
+        };
+        for &count in birth {
+            rule.birth[count as usize] = true;
+        }
+        for &count in survival {
+            rule.survival[count as usize] = true;
+        }
+        rule
+    }
+
+    #[test]
+    fn notations_are_equivalent() {
+        for rule in ["B3/S23", "b3/s23", "S23/B3", "23/3", " B3/S23 "] {
+            assert_eq!(parse(rule), Rule::conway(), "{rule}");
+        }
+        assert_eq!(parse("B36/S23"), parse("23/36"));
+        assert_eq!(parse("B2/S"), parse("/2"));
+    }
+
+    #[test]
+    fn display_round_trips() {
+        for (name, rule) in PRESETS {
+            let parsed = parse(rule);
+            assert_eq!(parse(&parsed.to_string()), parsed, "{name}");
+        }
+        for rule in [
+            "B3/S23",
+            "B36/S23",
+            "B/S012345678",
+            "B2/S",
+            "B2/S/C3",
+            "B2/S345/C4",
+        ] {
+            assert_eq!(parse(rule).to_string(), rule);
+        }
+        assert_eq!(parse("23/3").to_string(), "B3/S23");
+    }
+
+    #[test]
+    fn next_state_follows_counts() {
+        let rule = Rule::conway();
+        for neighbors in 0..=8 {
+            assert_eq!(rule.next_state(false, neighbors), neighbors == 3);
+            assert_eq!(
+                rule.next_state(true, neighbors),
+                neighbors == 2 || neighbors == 3
+            );
+        }
+        assert!(!rule.next_state(true, 9));
+    }
+
+    #[test]
+    fn large_counts_round_trip() {
+        for (birth, survival, text) in [
+            (&[10, 12][..], &[9][..], "B10,12/S9..9"),
+            (&[3, 10, 11, 12, 20][..], &[][..], "B3,10..12,20/S"),
+            (&[3][..], &[2, 3, 24][..], "B3/S2..3,24"),
+            (&[30][..], &[0][..], "B30..30/S0"),
+        ] {
+            let rule = totalistic(birth, survival);
+            assert_eq!(rule.to_string(), text);
+            assert_eq!(parse(text), rule, "{text}");
+        }
+        assert_eq!(totalistic(&[3], &[2, 3]).to_string(), "B3/S23");
+        assert_eq!(parse("10..12/9..9"), totalistic(&[9], &[10, 11, 12]));
+        assert_eq!(
+            "B10,x/S".parse::<Rule>(),
+            Err(RuleParseError::InvalidCount {
+                section: 'B',
+                found: "x".to_string()
+            })
+        );
+        assert!(matches!(
+            "B99999..99999/S".parse::<Rule>(),
+            Err(RuleParseError::CountOutOfRange { .. })
+        ));
+        // Un intervalo invertido no tiene ningún conteo: es un error, no una sección vacía
+        assert_eq!(
+            "B3/S2,12..10".parse::<Rule>(),
+            Err(RuleParseError::InvalidCount {
+                section: 'S',
+                found: "12..10".to_string()
+            })
+        );
+    }
+
+    #[test]
+    fn invalid_rules() {
+        let error = |rule: &str| rule.parse::<Rule>().unwrap_err();
+        assert_eq!(error(""), RuleParseError::Empty);
+        assert_eq!(
+            error("B3"),
+            RuleParseError::MissingSeparator("B3".to_string())
+        );
+        assert_eq!(
+            error("B3/S23/C3/X"),
+            RuleParseError::TooManySections("B3/S23/C3/X".to_string())
+        );
+        assert_eq!(
+            error("B39/S23"),
+            RuleParseError::InvalidCharacter {
+                section: 'B',
+                found: '9'
+            }
+        );
+        assert_eq!(error("B3/B2"), RuleParseError::DuplicateSection('B'));
+        assert_eq!(
+            error("B3/23"),
+            RuleParseError::MixedNotation("B3/23".to_string())
+        );
+        assert_eq!(
+            error("B2/S/C1"),
+            RuleParseError::InvalidStates("C1".to_string())
+        );
+    }
+
+    #[test]
+    fn larger_than_life_ranges() {
+        let rule = parse("R2,C0,M1,S3..5,B4,NN");
+        assert_eq!((rule.range, rule.include_center), (2, true));
+        assert_eq!(rule.neighborhood, Neighborhood::VonNeumann);
+        assert_eq!(rule.survival.iter().filter(|&&enabled| enabled).count(), 3);
+        assert!(rule.birth[4] && !rule.birth[3] && !rule.birth[5]);
+
+        // Los intervalos invertidos se rechazan en lugar de quedar vacíos
+        for item in ["S9..3", "B2..1"] {
+            assert_eq!(
+                format!("R2,C0,M0,{item},NM").parse::<Rule>(),
+                Err(RuleParseError::InvalidLargerThanLife(item.to_string()))
+            );
+        }
+    }
+}
@@ -0,0 +1,376 @@
+use crate::game_of_life::GameOfLife;
+use crate::sparse::SparseLife;
+use std::ops::{Deref, DerefMut};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+// Planificador de la simulación: avanza las generaciones en su propio hilo, separado del
+// bucle de dibujo, así la velocidad de la simulación no depende de lo que tarde cada frame.
+// Para dibujar, el bucle pide una copia de la simulación (snapshot): el candado solo se
+// toma mientras se copia y la simulación sigue avanzando mientras se dibuja la copia.
+
+// Simulaciones que puede avanzar el planificador
+pub trait Simulation: Clone + Send + 'static {
+    // Calcular la siguiente generación
+    fn step(&mut self);
+
+    // Avisar que la simulación ya se copió para dibujar un frame
+    fn presented(&mut self) {}
+}
+
+impl Simulation for GameOfLife {
+    fn step(&mut self) {
+        self.update();
+    }
+
+    // Los cambios ya quedaron en la copia que se dibuja: el siguiente frame solo
+    // redibuja los que ocurran desde ahora
+    fn presented(&mut self) {
+        self.clear_changes();
+    }
+}
+
+impl Simulation for SparseLife {
+    fn step(&mut self) {
+        self.update();
+    }
+}
+
+// Velocidad de la simulación
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Speed {
+    // Tan rápido como se pueda, sin esperar entre generaciones
+    Unlimited,
+    // Generaciones por segundo con paso fijo, sin importar cuántos frames se dibujan
+    PerSecond(f64),
+    // N generaciones por cada frame dibujado (0 = en pausa)
+    PerFrame(u32),
+}
+
+// Si la simulación se atrasa más que esto (por ejemplo, con una regla muy lenta), se
+// descartan las generaciones atrasadas en lugar de intentar recuperarlas todas
+const MAX_LAG: Duration = Duration::from_millis(250);
+
+struct State<T> {
+    simulation: T,
+    speed: Speed,
+    pending: u32, // Generaciones que pidió el último frame en el modo PerFrame
+    stop: bool,
+}
+
+struct Shared<T> {
+    state: Mutex<State<T>>,
+    changed: Condvar, // Se avisa cuando cambia la velocidad, se piden generaciones o hay que terminar
+    waiting: AtomicUsize, // Llamadas de lock y snapshot esperando el candado
+}
+
+impl<T> Shared<T> {
+    // Tomar el candado desde el bucle de dibujo: mientras espera, el hilo de la
+    // simulación le cede el turno entre generaciones
+    fn lock(&self) -> MutexGuard<'_, State<T>> {
+        self.waiting.fetch_add(1, Ordering::SeqCst);
+        let state = self.state.lock().expect("el hilo de la simulación falló");
+        self.waiting.fetch_sub(1, Ordering::SeqCst);
+        state
+    }
+
+    // Soltar el candado después de una generación y volver a tomarlo. Si el bucle de
+    // dibujo lo está esperando se le deja pasar primero: el Mutex no es justo y sin esto
+    // el modo Unlimited podría volver a tomarlo siempre antes.
+    fn relock<'a>(&'a self, state: MutexGuard<'a, State<T>>) -> MutexGuard<'a, State<T>> {
+        drop(state);
+        while self.waiting.load(Ordering::SeqCst) > 0 {
+            std::thread::yield_now();
+        }
+        self.state.lock().expect("el hilo de la simulación falló")
+    }
+}
+
+pub struct Scheduler<T: Simulation> {
+    shared: Arc<Shared<T>>,
+    thread: Option<JoinHandle<()>>,
+    frame: T, // Copia que se dibuja, reutilizada entre frames
+}
+
+impl<T: Simulation> Scheduler<T> {
+    pub fn new(simulation: T, speed: Speed) -> Self {
+        let frame = simulation.clone();
+        let shared = Arc::new(Shared {
+            state: Mutex::new(State { simulation, speed, pending: 0, stop: false }),
+            changed: Condvar::new(),
+            waiting: AtomicUsize::new(0),
+        });
+        let thread = {
+            let shared = Arc::clone(&shared);
+            std::thread::spawn(move || run(&shared))
+        };
+        Scheduler { shared, thread: Some(thread), frame }
+    }
+
+    pub fn speed(&self) -> Speed {
+        self.shared.lock().speed
+    }
+
+    pub fn set_speed(&self, speed: Speed) {
+        let mut state = self.shared.lock();
+        state.speed = speed;
+        state.pending = 0;
+        drop(state);
+        self.shared.changed.notify_all();
+    }
+
+    // Acceso exclusivo para modificar la simulación (la simulación espera mientras dure)
+    pub fn lock(&self) -> Guard<'_, T> {
+        Guard(self.shared.lock())
+    }
+
+    // Copia de la simulación para dibujar un frame. El candado solo se toma mientras se
+    // copia. En el modo PerFrame se piden las generaciones del frame siguiente, que el
+    // hilo de la simulación calcula mientras se dibuja esta copia; las que no alcanzó
+    // a calcular del frame anterior se descartan, como el atraso de PerSecond.
+    pub fn snapshot(&mut self) -> &T {
+        let mut state = self.shared.lock();
+        self.frame.clone_from(&state.simulation);
+        state.simulation.presented();
+        if let Speed::PerFrame(generations) = state.speed {
+            state.pending = generations;
+        }
+        drop(state);
+        self.shared.changed.notify_all();
+        &self.frame
+    }
+}
+
+impl<T: Simulation> Drop for Scheduler<T> {
+    fn drop(&mut self) {
+        self.shared.lock().stop = true;
+        self.shared.changed.notify_all();
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+// Paso fijo de PerSecond: si a la hora `now` ya toca la generación programada para
+// `next_step`, devuelve cuándo toca la siguiente (None si todavía no toca). Un atraso
+// mayor que MAX_LAG se descarta en lugar de recuperarse.
+fn next_tick(next_step: Instant, now: Instant, interval: Duration) -> Option<Instant> {
+    if now < next_step {
+        return None;
+    }
+    let next = next_step + interval;
+    if now.saturating_duration_since(next) > MAX_LAG {
+        Some(now)
+    } else {
+        Some(next)
+    }
+}
+
+// Bucle del hilo de la simulación
+fn run<T: Simulation>(shared: &Shared<T>) {
+    let mut next_step = Instant::now();
+    let mut state = shared.state.lock().expect("el hilo de la simulación falló");
+    while !state.stop {
+        match state.speed {
+            Speed::Unlimited => {
+                state.simulation.step();
+                state = shared.relock(state);
+                next_step = Instant::now();
+            }
+            Speed::PerSecond(rate) if rate > 0.0 => {
+                let now = Instant::now();
+                match next_tick(next_step, now, Duration::from_secs_f64(1.0 / rate)) {
+                    Some(next) => {
+                        state.simulation.step();
+                        next_step = next;
+                        state = shared.relock(state);
+                    }
+                    None => {
+                        state = shared
+                            .changed
+                            .wait_timeout(state, next_step - now)
+                            .expect("el hilo de la simulación falló")
+                            .0;
+                    }
+                }
+            }
+            Speed::PerFrame(_) if state.pending > 0 => {
+                state.simulation.step();
+                state.pending -= 1;
+                state = shared.relock(state);
+            }
+            // En pausa, sin generaciones pedidas o con 0 por segundo: esperar a que
+            // cambie la velocidad o un frame pida generaciones
+            _ => {
+                state = shared.changed.wait(state).expect("el hilo de la simulación falló");
+                next_step = Instant::now();
+            }
+        }
+    }
+}
+
+// Acceso exclusivo a la simulación
+pub struct Guard<'a, T>(MutexGuard<'a, State<T>>);
+
+impl<T> Deref for Guard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0.simulation
+    }
+}
+
+impl<T> DerefMut for Guard<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.0.simulation
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Simulación que solo cuenta sus generaciones y los frames copiados
+    #[derive(Clone, Default)]
+    struct Counter {
+        steps: u64,
+        presented: u64,
+    }
+
+    impl Simulation for Counter {
+        fn step(&mut self) {
+            self.steps += 1;
+        }
+
+        fn presented(&mut self) {
+            self.presented += 1;
+        }
+    }
+
+    // Esperar (como mucho 5 segundos) a que la simulación cumpla una condición
+    fn wait_until(scheduler: &Scheduler<Counter>, condition: impl Fn(&Counter) -> bool) {
+        let start = Instant::now();
+        while !condition(&scheduler.lock()) {
+            assert!(start.elapsed() < Duration::from_secs(5), "la simulación no avanzó");
+            std::thread::sleep(Duration::from_millis(1));
+        }
+    }
+
+    #[test]
+    fn per_frame_steps_on_the_simulation_thread() {
+        let mut scheduler = Scheduler::new(Counter::default(), Speed::PerFrame(3));
+        // El primer frame muestra el estado inicial y pide 3 generaciones
+        assert_eq!(scheduler.snapshot().steps, 0);
+        wait_until(&scheduler, |counter| counter.steps == 3);
+        // Cada frame muestra las generaciones pedidas por el anterior
+        for frame in 1..=5 {
+            assert_eq!(scheduler.snapshot().steps, 3 * frame);
+            wait_until(&scheduler, |counter| counter.steps == 3 * (frame + 1));
+        }
+        std::thread::sleep(Duration::from_millis(20));
+        assert_eq!(scheduler.lock().steps, 18);
+        assert_eq!(scheduler.lock().presented, 6);
+    }
+
+    #[test]
+    fn pause_and_resume() {
+        let mut scheduler = Scheduler::new(Counter::default(), Speed::PerFrame(0));
+        for _ in 0..5 {
+            scheduler.snapshot();
+        }
+        std::thread::sleep(Duration::from_millis(20));
+        assert_eq!(scheduler.lock().steps, 0);
+
+        // Un frame en PerFrame(1) avanza exactamente una generación
+        scheduler.set_speed(Speed::PerFrame(1));
+        scheduler.snapshot();
+        wait_until(&scheduler, |counter| counter.steps == 1);
+        scheduler.set_speed(Speed::PerFrame(0));
+        std::thread::sleep(Duration::from_millis(20));
+        assert_eq!(scheduler.lock().steps, 1);
+
+        // Con 0 generaciones por segundo también queda en pausa
+        scheduler.set_speed(Speed::PerSecond(0.0));
+        std::thread::sleep(Duration::from_millis(20));
+        assert_eq!(scheduler.lock().steps, 1);
+    }
+
+    #[test]
+    fn snapshot_is_a_copy() {
+        let mut scheduler = Scheduler::new(Counter::default(), Speed::PerFrame(1));
+        scheduler.snapshot();
+        wait_until(&scheduler, |counter| counter.steps == 1);
+        // La copia del frame no cambia aunque la simulación siga avanzando
+        scheduler.lock().steps = 100;
+        assert_eq!(scheduler.frame.steps, 0);
+        assert_eq!(scheduler.snapshot().steps, 100);
+    }
+
+    #[test]
+    fn unlimited_lets_the_renderer_in() {
+        // El hilo de la simulación nunca espera, pero cada lock y cada snapshot del bucle
+        // de dibujo entra enseguida
+        let mut scheduler = Scheduler::new(Counter::default(), Speed::Unlimited);
+        let start = Instant::now();
+        let mut last = 0;
+        for _ in 0..50 {
+            let steps = scheduler.snapshot().steps;
+            assert!(steps >= last);
+            last = scheduler.lock().steps;
+        }
+        assert!(start.elapsed() < Duration::from_secs(2), "{:?}", start.elapsed());
+        wait_until(&scheduler, |counter| counter.steps > last);
+        assert_eq!(scheduler.lock().presented, 50);
+    }
+
+    #[test]
+    fn per_second_runs_in_real_time() {
+        let scheduler = Scheduler::new(Counter::default(), Speed::PerSecond(100.0));
+        std::thread::sleep(Duration::from_millis(200));
+        let steps = scheduler.lock().steps;
+        assert!((5..=40).contains(&steps), "{steps} generaciones en 200 ms");
+    }
+
+    #[test]
+    fn fixed_step_ticks() {
+        let start = Instant::now();
+        let interval = Duration::from_millis(100);
+        let at = |millis| start + Duration::from_millis(millis);
+
+        // Antes de la hora programada no toca; en la hora exacta sí
+        assert_eq!(next_tick(at(100), at(50), interval), None);
+        assert_eq!(next_tick(at(100), at(100), interval), Some(at(200)));
+
+        // Revisando cada 10 ms durante un segundo se calculan 10 generaciones, sin que
+        // se pierda ni se repita ninguna aunque los tiempos no coincidan con el paso
+        let mut next_step = at(0);
+        let mut steps = 0;
+        for millis in (5..1000).step_by(10) {
+            while let Some(next) = next_tick(next_step, at(millis), interval) {
+                next_step = next;
+                steps += 1;
+            }
+        }
+        assert_eq!(steps, 10);
+
+        // Un atraso pequeño se recupera con generaciones seguidas
+        let mut next_step = at(0);
+        let mut steps = 0;
+        while let Some(next) = next_tick(next_step, at(200), interval) {
+            next_step = next;
+            steps += 1;
+        }
+        assert_eq!((steps, next_step), (3, at(300)));
+
+        // Un atraso de más de MAX_LAG se descarta: se sigue desde ahora
+        let mut next_step = at(0);
+        let mut steps = 0;
+        while let Some(next) = next_tick(next_step, at(1000), interval) {
+            next_step = next;
+            steps += 1;
+        }
+        assert_eq!((steps, next_step), (2, at(1100)));
+    }
+}
@@ -0,0 +1,604 @@
+use crate::bitgrid::BitGrid;
+use crate::framebuffer::Framebuffer;
+use crate::game_of_life::{self, CellColor, ColorLayer, ColorMode, ColorSum, GameOfLife, Hsv, Hue};
+use crate::inheritance::{CircularMean, ColorInheritance, Parent};
+use crate::kernel::Kernel;
+use crate::palette::Palette;
+use crate::random;
+use crate::rule::{Neighborhood, Rule};
+use raylib::prelude::*;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+// Lado de cada bloque de células. Debe ser al menos el radio máximo de las reglas
+// (MAX_RANGE) para que la vecindad de un bloque solo toque a sus 8 vecinos.
+pub const CHUNK_SIZE: i64 = 64;
+
+const CHUNK_CELLS: usize = (CHUNK_SIZE * CHUNK_SIZE) as usize;
+
+// Bloque de CHUNK_SIZE x CHUNK_SIZE células. Las posiciones dentro del bloque son
+// índices por filas (y * CHUNK_SIZE + x).
+#[derive(Clone)]
+struct Chunk {
+    alive: BitGrid,
+    colors: ColorLayer, // Solo las células vivas o muriendo con color tienen entrada
+    decay: Vec<u8>,     // 0 = viva o muerta, k > 0 = k-ésimo estado de decaimiento
+    born: Vec<u64>,     // Generación en la que nació cada célula viva o muriendo
+}
+
+impl Chunk {
+    fn new() -> Self {
+        Chunk {
+            alive: BitGrid::new(CHUNK_SIZE as usize, CHUNK_SIZE as usize),
+            colors: ColorLayer::default(),
+            decay: vec![0; CHUNK_CELLS],
+            born: vec![0; CHUNK_CELLS],
+        }
+    }
+
+    fn is_alive(&self, local: usize) -> bool {
+        self.alive.get(local % CHUNK_SIZE as usize, local / CHUNK_SIZE as usize)
+    }
+
+    fn set_alive(&mut self, local: usize, alive: bool) {
+        self.alive.set(local % CHUNK_SIZE as usize, local / CHUNK_SIZE as usize, alive);
+    }
+
+    fn color(&self, local: usize) -> Option<CellColor> {
+        self.colors.get(&local).copied()
+    }
+
+    fn set_color(&mut self, local: usize, color: Option<CellColor>) {
+        match color {
+            Some(color) => self.colors.insert(local, color),
+            None => self.colors.remove(&local),
+        };
+    }
+
+    // Un bloque sin células vivas ni muriendo se puede liberar
+    fn is_empty(&self) -> bool {
+        self.alive.count_ones() == 0 && !self.decay.iter().any(|&decay| decay > 0)
+    }
+}
+
+// Copia de las células vivas (con sus colores y la generación en la que nacieron) del
+// rectángulo de un bloque más un margen. Se reutiliza para todos los bloques de una
+// generación: solo se borran las posiciones que se escribieron.
+struct Window {
+    pad: i64,
+    side: i64,
+    alive: Vec<bool>,
+    colors: Vec<Option<CellColor>>,
+    born: Vec<u64>,
+    written: Vec<usize>, // Posiciones con una célula viva copiada
+}
+
+impl Window {
+    fn new(pad: i64) -> Self {
+        let side = CHUNK_SIZE + 2 * pad;
+        let cells = (side * side) as usize;
+        Window {
+            pad,
+            side,
+            alive: vec![false; cells],
+            colors: vec![None; cells],
+            born: vec![0; cells],
+            written: Vec::new(),
+        }
+    }
+
+    // Posición en la ventana de la célula (x, y) relativa a la esquina del bloque
+    fn at(&self, x: i64, y: i64) -> usize {
+        ((y + self.pad) * self.side + x + self.pad) as usize
+    }
+
+    fn is_empty(&self) -> bool {
+        self.written.is_empty()
+    }
+
+    // Copiar la vecindad del bloque `key`
+    fn fill(&mut self, chunks: &HashMap<(i64, i64), Chunk>, key: (i64, i64)) {
+        for index in self.written.drain(..) {
+            self.alive[index] = false;
+            self.colors[index] = None;
+        }
+        let left = key.0 * CHUNK_SIZE - self.pad;
+        let top = key.1 * CHUNK_SIZE - self.pad;
+
+        // Recorrer solo los bloques que se solapan con la ventana
+        let reach = (self.pad + CHUNK_SIZE - 1) / CHUNK_SIZE;
+        for cy in key.1 - reach..=key.1 + reach {
+            for cx in key.0 - reach..=key.0 + reach {
+                let Some(chunk) = chunks.get(&(cx, cy)) else {
+                    continue;
+                };
+                let x0 = (cx * CHUNK_SIZE).max(left);
+                let x1 = ((cx + 1) * CHUNK_SIZE).min(left + self.side);
+                let y0 = (cy * CHUNK_SIZE).max(top);
+                let y1 = ((cy + 1) * CHUNK_SIZE).min(top + self.side);
+                for y in y0..y1 {
+                    for x in x0..x1 {
+                        let local =
+                            ((y - cy * CHUNK_SIZE) * CHUNK_SIZE + x - cx * CHUNK_SIZE) as usize;
+                        if chunk.is_alive(local) {
+                            let index = ((y - top) * self.side + x - left) as usize;
+                            self.alive[index] = true;
+                            self.colors[index] = chunk.color(local);
+                            self.born[index] = chunk.born[local];
+                            self.written.push(index);
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+// Plano infinito: solo se guardan los bloques que tienen células, indexados por sus
+// coordenadas de bloque, así los patrones que crecen (cañones, puffers) nunca se recortan
+// y las coordenadas pueden ser negativas.
+#[derive(Clone)]
+pub struct SparseLife {
+    chunks: HashMap<(i64, i64), Chunk>,
+    pub palette: Palette, // Fondo, color de las células sin color y degradado de los modos de color
+    pub color_mode: ColorMode,
+    pub age_scale: u64, // Edad a la que los modos por edad llegan al final del degradado
+    pub color_variation: f32, // Variación del matiz de las células que nacen
+    pub saturation_variation: f32,
+    pub value_variation: f32,
+    pub rule: Rule,
+    pub kernel: Option<Kernel>, // Vecindad personalizada que reemplaza la de la regla
+    pub inheritance: Arc<dyn ColorInheritance>, // Cómo hereda el color una célula que nace
+    pub generation: u64,
+    seed: u64, // Semilla de los colores al azar y de la variación de color
+}
+
+impl Default for SparseLife {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Coordenadas de bloque y posición dentro del bloque de una célula
+fn locate(x: i64, y: i64) -> ((i64, i64), usize) {
+    let chunk = (x.div_euclid(CHUNK_SIZE), y.div_euclid(CHUNK_SIZE));
+    let local = y.rem_euclid(CHUNK_SIZE) * CHUNK_SIZE + x.rem_euclid(CHUNK_SIZE);
+    (chunk, local as usize)
+}
+
+impl SparseLife {
+    pub fn new() -> Self {
+        Self::with_seed(random::time_seed())
+    }
+
+    // Crear el plano con una semilla fija (la misma semilla da la misma simulación)
+    pub fn with_seed(seed: u64) -> Self {
+        SparseLife {
+            chunks: HashMap::new(),
+            palette: Palette::default(),
+            color_mode: ColorMode::Inherited,
+            age_scale: 1000,
+            color_variation: 0.05,
+            saturation_variation: 0.0,
+            value_variation: 0.0,
+            rule: Rule::conway(),
+            kernel: None,
+            inheritance: Arc::new(CircularMean),
+            generation: 0,
+            seed,
+        }
+    }
+
+    // Copiar el contenido de un grid acotado, con su regla, sus colores y sus edades (la
+    // cuenta de generaciones sigue desde la del grid)
+    pub fn from_game(game: &GameOfLife) -> Self {
+        let mut sparse = SparseLife::with_seed(game.seed());
+        sparse.palette = game.palette.clone();
+        sparse.color_mode = game.color_mode;
+        sparse.age_scale = game.age_scale;
+        sparse.color_variation = game.color_variation;
+        sparse.saturation_variation = game.saturation_variation;
+        sparse.value_variation = game.value_variation;
+        sparse.rule = game.rule.clone();
+        sparse.kernel = game.kernel.clone();
+        sparse.inheritance = game.inheritance.clone();
+        sparse.generation = game.generation;
+
+        for x in 0..game.width as usize {
+            for y in 0..game.height as usize {
+                let state = game.get_cell_state(x, y);
+                if state == 0 {
+                    continue;
+                }
+                let (chunk, local) = locate(x as i64, y as i64);
+                let chunk = sparse.chunks.entry(chunk).or_insert_with(Chunk::new);
+                chunk.set_alive(local, state == 1);
+                chunk.decay[local] = state.saturating_sub(1);
+                chunk.set_color(local, game.get_cell_hsv(x, y).map(CellColor::new));
+                chunk.born[local] = game.generation - game.get_cell_age(x, y).unwrap_or(0);
+            }
+        }
+        sparse
+    }
+
+    // Radio que alcanza la vecindad actual (regla o kernel)
+    fn reach(&self) -> i64 {
+        match &self.kernel {
+            Some(kernel) => kernel.radius() as i64,
+            None => self.rule.range as i64,
+        }
+    }
+
+    // Desplazamientos (dx, dy, peso) de la vecindad para filas pares e impares; solo la
+    // red hexagonal distingue entre ellas
+    fn offsets(&self) -> [Vec<(i64, i64, u32)>; 2] {
+        if let Some(kernel) = &self.kernel {
+            let cells: Vec<_> = kernel
+                .cells
+                .iter()
+                .map(|cell| (cell.dx as i64, cell.dy as i64, cell.weight))
+                .collect();
+            return [cells.clone(), cells];
+        }
+
+        let r = self.rule.range as i64;
+        std::array::from_fn(|parity| {
+            let mut offsets = Vec::new();
+            for dy in -r..=r {
+                for dx in -r..=r {
+                    let inside = match self.rule.neighborhood {
+                        Neighborhood::Moore => true,
+                        Neighborhood::VonNeumann => dx.abs() + dy.abs() <= r,
+                        // Filas impares desplazadas media célula a la derecha
+                        Neighborhood::Hexagonal => match dy {
+                            0 => true,
+                            _ => dx == parity as i64 || dx == parity as i64 - 1,
+                        },
+                    };
+                    if inside && ((dx, dy) != (0, 0) || self.rule.include_center) {
+                        offsets.push((dx, dy, 1));
+                    }
+                }
+            }
+            offsets
+        })
+    }
+
+    // Calcular la siguiente generación de un bloque (None si queda vacío)
+    fn step_chunk(
+        &self,
+        key: (i64, i64),
+        window: &mut Window,
+        offsets: &[Vec<(i64, i64, u32)>; 2],
+    ) -> Option<Chunk> {
+        window.fill(&self.chunks, key);
+        let current = self.chunks.get(&key);
+        if current.is_none() && window.is_empty() {
+            return None;
+        }
+
+        let Window { alive, colors, born, .. } = &*window;
+        let at = |x: i64, y: i64| window.at(x, y);
+        let use_table = self.kernel.is_none() && self.rule.table.is_some();
+        let mut next = Chunk::new();
+
+        for ly in 0..CHUNK_SIZE {
+            let y = key.1 * CHUNK_SIZE + ly;
+            let offsets = &offsets[y.rem_euclid(2) as usize];
+            for lx in 0..CHUNK_SIZE {
+                let local = (ly * CHUNK_SIZE + lx) as usize;
+                let is_alive = alive[at(lx, ly)];
+                let decay = current.map_or(0, |chunk| chunk.decay[local]);
+
+                // Célula muriendo (reglas Generations): avanza un estado hasta morir
+                if decay > 0 {
+                    if decay < self.rule.dying_states() {
+                        next.decay[local] = decay + 1;
+                        next.set_color(local, current.and_then(|chunk| chunk.color(local)));
+                        next.born[local] = current.map_or(0, |chunk| chunk.born[local]);
+                    }
+                    continue;
+                }
+
+                let will_live = if use_table {
+                    let mut index = 0;
+                    for dy in -1..=1 {
+                        for dx in -1..=1 {
+                            index = (index << 1) | alive[at(lx + dx, ly + dy)] as usize;
+                        }
+                    }
+                    self.rule.next_state_from_index(index)
+                } else {
+                    let neighbors = offsets
+                        .iter()
+                        .filter(|&&(dx, dy, _)| alive[at(lx + dx, ly + dy)])
+                        .map(|&(_, _, weight)| weight)
+                        .sum();
+                    self.rule.next_state(is_alive, neighbors)
+                };
+
+                if will_live {
+                    next.set_alive(local, true);
+                    if is_alive {
+                        // Célula sobrevive, mantiene su color y su edad
+                        next.set_color(local, colors[at(lx, ly)]);
+                        next.born[local] = born[at(lx, ly)];
+                    } else {
+                        // Nueva célula nace, hereda el color de sus vecinos con la estrategia elegida
+                        let x = key.0 * CHUNK_SIZE + lx;
+                        let parent = if self.inheritance.is_circular_mean() {
+                            let mut sum = ColorSum::default();
+                            for &(dx, dy, weight) in offsets {
+                                if let Some(color) = &colors[at(lx + dx, ly + dy)] {
+                                    sum.add(color, weight);
+                                }
+                            }
+                            sum.average()
+                        } else {
+                            let mut parents = offsets.iter().filter_map(|&(dx, dy, weight)| {
+                                let index = at(lx + dx, ly + dy);
+                                Some(Parent { color: colors[index]?, weight, age: self.generation - born[index] })
+                            });
+                            let seed = random::cell_random(self.seed, self.generation, x, y, random::STREAM_PARENT);
+                            self.inheritance.inherit(&mut parents, seed)
+                        };
+                        next.set_color(local, Some(CellColor::new(self.vary_color(parent, x, y))));
+                        next.born[local] = self.generation + 1;
+                    }
+                } else if is_alive && self.rule.is_generations() {
+                    // Célula empieza a morir y conserva su color y su edad mientras se desvanece
+                    next.decay[local] = 1;
+                    next.set_color(local, colors[at(lx, ly)]);
+                    next.born[local] = born[at(lx, ly)];
+                }
+            }
+        }
+
+        (!next.is_empty()).then_some(next)
+    }
+
+    // Agregar la variación de cada componente del color según la posición y la generación
+    // (sin padres con color, un matiz al azar); depende solo de la semilla, no del orden
+    // en que se calculan
+    fn vary_color(&self, parent: Option<Hsv>, x: i64, y: i64) -> Hsv {
+        let random = |stream| random::unit(random::cell_random(self.seed, self.generation, x, y, stream));
+        let Some(parent) = parent else {
+            return Hue::new(random(random::STREAM_HUE)).into();
+        };
+        let vary = |amount: f32, stream| {
+            if amount > 0.0 {
+                (random(stream) * 2.0 - 1.0) * amount
+            } else {
+                0.0
+            }
+        };
+        Hsv::new(
+            Hue::new(parent.hue.value + vary(self.color_variation, random::STREAM_VARIATION)),
+            parent.saturation + vary(self.saturation_variation, random::STREAM_SATURATION),
+            parent.value + vary(self.value_variation, random::STREAM_VALUE),
+        )
+    }
+
+    // Avanzar una generación. Se calculan los bloques existentes y los que los rodean;
+    // los bloques que quedan vacíos se liberan. Las reglas con B0 no tienen sentido en
+    // el plano infinito (nacerían células en todo el fondo) y solo afectan a esos bloques.
+    pub fn update(&mut self) {
+        let pad = self.reach();
+        let offsets = self.offsets();
+        let reach = (pad + CHUNK_SIZE - 1) / CHUNK_SIZE;
+
+        let mut candidates: Vec<(i64, i64)> = self
+            .chunks
+            .keys()
+            .flat_map(|&(cx, cy)| {
+                (-reach..=reach)
+                    .flat_map(move |dy| (-reach..=reach).map(move |dx| (cx + dx, cy + dy)))
+            })
+            .collect();
+        candidates.sort_unstable();
+        candidates.dedup();
+
+        let mut window = Window::new(pad);
+        let chunks = candidates
+            .into_iter()
+            .filter_map(|key| Some((key, self.step_chunk(key, &mut window, &offsets)?)))
+            .collect();
+        self.chunks = chunks;
+        self.generation += 1;
+    }
+
+    // Color con el que se dibuja una célula según su estado, igual que en GameOfLife
+    fn cell_color(&self, x: i64, y: i64) -> Color {
+        let (key, local) = locate(x, y);
+        let Some(chunk) = self.chunks.get(&key) else {
+            return self.palette.background;
+        };
+        let decay = chunk.decay[local];
+        if !chunk.is_alive(local) && decay == 0 {
+            return self.palette.background;
+        }
+        let fade = 1.0 - decay as f32 / (self.rule.states - 1) as f32;
+        let age = self.generation - chunk.born[local];
+        self.color_mode.cell_color(&self.palette, chunk.colors.get(&local), fade, || {
+            game_of_life::age_fraction(age, self.age_scale)
+        })
+    }
+
+    // Dibujar la ventana de `columns` x `rows` células cuya esquina superior izquierda
+    // es la célula (view_x, view_y)
+    pub fn render(
+        &self,
+        framebuffer: &mut Framebuffer,
+        (view_x, view_y): (i64, i64),
+        (columns, rows): (u32, u32),
+        (offset_x, offset_y): (u32, u32),
+        scale: u32,
+    ) {
+        let mut line = vec![Color::BLACK; columns as usize];
+        for row in 0..rows {
+            let y = view_y + row as i64;
+            for (column, color) in line.iter_mut().enumerate() {
+                *color = self.cell_color(view_x + column as i64, y);
+            }
+            framebuffer.blit_scaled(offset_x, offset_y + row * scale, columns, 1, &line, scale);
+        }
+    }
+
+    // Obtener estadísticas: células vivas y bloques en memoria
+    pub fn get_stats(&self) -> (u32, u32) {
+        let alive = self
+            .chunks
+            .values()
+            .map(|chunk| chunk.alive.count_ones())
+            .sum();
+        (alive, self.chunks.len() as u32)
+    }
+
+    // Cambiar la regla en tiempo de ejecución
+    pub fn set_rule(&mut self, rule: Rule) {
+        // Las células en estados que la nueva regla no tiene mueren directamente
+        for chunk in self.chunks.values_mut() {
+            for local in 0..CHUNK_CELLS {
+                if chunk.decay[local] > rule.dying_states() {
+                    chunk.decay[local] = 0;
+                    chunk.colors.remove(&local);
+                }
+            }
+        }
+        self.chunks.retain(|_, chunk| !chunk.is_empty());
+        self.rule = rule;
+    }
+
+    // Usar una vecindad personalizada (None vuelve a la vecindad de la regla)
+    pub fn set_kernel(&mut self, kernel: Option<Kernel>) {
+        self.kernel = kernel;
+    }
+
+    // Cambiar lo que representa el color de cada célula
+    pub fn set_color_mode(&mut self, mode: ColorMode) {
+        self.color_mode = mode;
+    }
+
+    // Cambiar la forma en que las células que nacen heredan el color
+    pub fn set_color_inheritance(&mut self, inheritance: Arc<dyn ColorInheritance>) {
+        self.inheritance = inheritance;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::inheritance;
+    use crate::random::Rng;
+
+    // Estado de una célula: 0 = muerta, 1 = viva, 2.. = muriendo
+    fn state(sparse: &SparseLife, x: i64, y: i64) -> u8 {
+        let (key, local) = locate(x, y);
+        match sparse.chunks.get(&key) {
+            Some(chunk) if chunk.is_alive(local) => 1,
+            Some(chunk) if chunk.decay[local] > 0 => chunk.decay[local] + 1,
+            _ => 0,
+        }
+    }
+
+    fn hsv(sparse: &SparseLife, x: i64, y: i64) -> Option<Hsv> {
+        let (key, local) = locate(x, y);
+        sparse.chunks.get(&key)?.color(local).map(|color| color.hsv)
+    }
+
+    fn age(sparse: &SparseLife, x: i64, y: i64) -> Option<u64> {
+        let (key, local) = locate(x, y);
+        (state(sparse, x, y) > 0).then(|| sparse.generation - sparse.chunks[&key].born[local])
+    }
+
+    // Grid de 128x128 con una sopa en el centro: en 20 generaciones no llega a los bordes,
+    // así el plano infinito debe dar exactamente lo mismo que el grid
+    fn centered_soup(rule: &str) -> GameOfLife {
+        let mut game = GameOfLife::with_seed(128, 128, 9);
+        game.set_rule_str(rule).unwrap();
+        game.set_color_variation(0.1);
+        let mut rng = Rng::new(4);
+        for y in 48..80 {
+            for x in 48..80 {
+                if rng.next_u64().is_multiple_of(3) {
+                    game.set_cell_with_color(x, y, true, Some(Hue::new(random::unit(rng.next_u64()))));
+                }
+            }
+        }
+        game
+    }
+
+    #[test]
+    fn glider_crosses_chunks() {
+        // Planeador hacia arriba a la izquierda: cada 4 generaciones avanza (-1, -1)
+        let glider: [(i64, i64); 5] = [(1, 1), (2, 1), (3, 1), (1, 2), (2, 3)];
+        let mut game = GameOfLife::with_seed(8, 8, 3);
+        for (x, y) in glider {
+            game.set_cell_with_color(x as usize, y as usize, true, Some(Hue::new(0.3)));
+        }
+        let mut sparse = SparseLife::from_game(&game);
+        for _ in 0..4 * 70 {
+            sparse.update();
+        }
+        for y in -80..10 {
+            for x in -80..10 {
+                let alive = glider.contains(&(x + 70, y + 70));
+                assert_eq!(state(&sparse, x, y), alive as u8, "({x}, {y})");
+                assert_eq!(hsv(&sparse, x, y).is_some(), alive, "({x}, {y})");
+            }
+        }
+        assert_eq!(sparse.get_stats().0, 5);
+        assert!(sparse.get_stats().1 <= 4, "{} bloques", sparse.get_stats().1);
+    }
+
+    #[test]
+    fn inheritance_matches_bounded_grid() {
+        for rule in ["B3/S23", "B2/S345/C4"] {
+            for strategy in inheritance::builtin() {
+                let mut game = centered_soup(rule);
+                game.set_color_inheritance(strategy.clone());
+                game.update();
+                let mut sparse = SparseLife::from_game(&game);
+                for _ in 0..20 {
+                    game.update();
+                    sparse.update();
+                }
+                assert_eq!(sparse.generation, game.generation);
+                for y in 0..128 {
+                    for x in 0..128 {
+                        let label = format!("{rule} {} ({x}, {y})", strategy.name());
+                        let (sx, sy) = (x as i64, y as i64);
+                        assert_eq!(state(&sparse, sx, sy), game.get_cell_state(x, y), "{label}");
+                        let hue = |hsv: Option<Hsv>| hsv.map(|hsv| (hsv.hue.value * 1e4).round() as u32);
+                        assert_eq!(hue(hsv(&sparse, sx, sy)), hue(game.get_cell_hsv(x, y)), "{label}");
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn colors_match_bounded_grid() {
+        for mode in [ColorMode::Inherited, ColorMode::Hue, ColorMode::Age, ColorMode::AgeShaded, ColorMode::State] {
+            let mut game = centered_soup("B2/S345/C4");
+            game.set_color_mode(mode);
+            game.set_palette(Palette::viridis());
+            game.age_scale = 8;
+            let mut sparse = SparseLife::from_game(&game);
+            for _ in 0..12 {
+                game.update();
+                sparse.update();
+            }
+            for y in 0..128 {
+                for x in 0..128 {
+                    assert_eq!(age(&sparse, x as i64, y as i64), game.get_cell_age(x, y), "{mode:?} ({x}, {y})");
+                }
+            }
+            let mut expected = Framebuffer::new(128, 128, Color::BLACK);
+            game.render(&mut expected, 0, 0, 1);
+            let mut rendered = Framebuffer::new(128, 128, Color::BLACK);
+            sparse.render(&mut rendered, (0, 0), (128, 128), (0, 0), 1);
+            assert!(rendered.pixels() == expected.pixels(), "{mode:?}");
+        }
+    }
+}
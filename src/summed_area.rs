@@ -0,0 +1,201 @@
+use crate::bitgrid::BitGrid;
+use crate::boundary::Boundary;
+use crate::game_of_life::{ColorLayer, Hsv, Hue};
+use crate::rule::{Neighborhood, Rule};
+use std::f64::consts::PI;
+
+// Tablas de áreas sumadas para contar vecinos en vecindades grandes (Larger than Life)
+// y en la red hexagonal.
+// Cada tabla guarda la suma de todas las células en el rectángulo [0, x) x [0, y),
+// así cualquier rectángulo se suma con cuatro accesos sin importar el radio.
+// El grid se rodea con un margen de `padding` células leídas según la condición de
+// frontera, así las vecindades que cruzan el borde se suman igual que las interiores.
+pub struct SummedAreaTable {
+    width: usize,  // Ancho con margen
+    height: usize, // Alto con margen
+    padding: usize,
+    alive: Vec<u32>,
+    colored: Vec<u32>,
+    cos: Vec<f64>, // Suma de los vectores unitarios de los matices (componente x)
+    sin: Vec<f64>, // Suma de los vectores unitarios de los matices (componente y)
+    saturation: Vec<f64>,
+    value: Vec<f64>,
+}
+
+impl SummedAreaTable {
+    pub fn new(cells: &BitGrid, colors: &ColorLayer, padding: usize, boundary: Boundary) -> Self {
+        let (inner_width, inner_height) = (cells.width(), cells.height());
+        let (width, height) = (inner_width + 2 * padding, inner_height + 2 * padding);
+        let size = (width + 1) * (height + 1);
+        let mut table = SummedAreaTable {
+            width,
+            height,
+            padding,
+            alive: vec![0; size],
+            colored: vec![0; size],
+            cos: vec![0.0; size],
+            sin: vec![0.0; size],
+            saturation: vec![0.0; size],
+            value: vec![0.0; size],
+        };
+
+        for y in 0..height {
+            let mut row_alive = 0;
+            let mut row_colored = 0;
+            let mut row_cos = 0.0;
+            let mut row_sin = 0.0;
+            let mut row_saturation = 0.0;
+            let mut row_value = 0.0;
+
+            for x in 0..width {
+                let cell = boundary.resolve(
+                    x as i64 - padding as i64,
+                    y as i64 - padding as i64,
+                    inner_width,
+                    inner_height,
+                );
+                if let Some((cx, cy)) = cell
+                    && cells.get(cx, cy)
+                {
+                    row_alive += 1;
+                    if let Some(color) = colors.get(&(cy * inner_width + cx)) {
+                        let (cos, sin) = color.unit();
+                        row_colored += 1;
+                        row_cos += cos as f64;
+                        row_sin += sin as f64;
+                        row_saturation += color.hsv.saturation as f64;
+                        row_value += color.hsv.value as f64;
+                    }
+                }
+
+                let above = table.index(x + 1, y);
+                let here = table.index(x + 1, y + 1);
+                table.alive[here] = table.alive[above] + row_alive;
+                table.colored[here] = table.colored[above] + row_colored;
+                table.cos[here] = table.cos[above] + row_cos;
+                table.sin[here] = table.sin[above] + row_sin;
+                table.saturation[here] = table.saturation[above] + row_saturation;
+                table.value[here] = table.value[above] + row_value;
+            }
+        }
+
+        table
+    }
+
+    fn index(&self, x: usize, y: usize) -> usize {
+        y * (self.width + 1) + x
+    }
+
+    // Sumar los valores del rectángulo [x0, x1) x [y0, y1)
+    fn rect_sum<T>(&self, data: &[T], x0: usize, y0: usize, x1: usize, y1: usize) -> T
+    where
+        T: Copy + std::ops::Add<Output = T> + std::ops::Sub<Output = T>,
+    {
+        data[self.index(x1, y1)] + data[self.index(x0, y0)]
+            - data[self.index(x0, y1)]
+            - data[self.index(x1, y0)]
+    }
+
+    // Recorrer los rectángulos que forman la vecindad de (x, y), recortados al grid con margen
+    fn for_each_rect(
+        &self,
+        x: usize,
+        y: usize,
+        rule: &Rule,
+        mut visit: impl FnMut(usize, usize, usize, usize),
+    ) {
+        let r = rule.range as i64;
+        // La paridad de la fila (red hexagonal) se toma de la coordenada sin margen
+        let shift = y as i64 & 1;
+        let (x, y) = ((x + self.padding) as i64, (y + self.padding) as i64);
+        let clamp_x = |value: i64| value.clamp(0, self.width as i64) as usize;
+        let clamp_y = |value: i64| value.clamp(0, self.height as i64) as usize;
+
+        match rule.neighborhood {
+            Neighborhood::Moore => {
+                visit(
+                    clamp_x(x - r),
+                    clamp_y(y - r),
+                    clamp_x(x + r + 1),
+                    clamp_y(y + r + 1),
+                );
+            }
+            Neighborhood::VonNeumann => {
+                // Una fila por cada dy, de ancho 2(r - |dy|) + 1
+                for dy in -r..=r {
+                    let row = y + dy;
+                    if row < 0 || row >= self.height as i64 {
+                        continue;
+                    }
+                    let half = r - dy.abs();
+                    visit(
+                        clamp_x(x - half),
+                        row as usize,
+                        clamp_x(x + half + 1),
+                        row as usize + 1,
+                    );
+                }
+            }
+            Neighborhood::Hexagonal => {
+                // Filas impares desplazadas media célula a la derecha: los vecinos de
+                // arriba y abajo son (x - 1, x) en filas pares y (x, x + 1) en impares
+                for dy in -1..=1 {
+                    let row = y + dy;
+                    if row < 0 || row >= self.height as i64 {
+                        continue;
+                    }
+                    let (left, right) = if dy == 0 {
+                        (x - 1, x + 1)
+                    } else {
+                        (x - 1 + shift, x + shift)
+                    };
+                    visit(
+                        clamp_x(left),
+                        row as usize,
+                        clamp_x(right + 1),
+                        row as usize + 1,
+                    );
+                }
+            }
+        }
+    }
+
+    // Contar las células vivas de la vecindad, incluyendo la central solo si la regla lo pide
+    pub fn count(&self, x: usize, y: usize, rule: &Rule, is_alive: bool) -> u32 {
+        let mut count = 0;
+        self.for_each_rect(x, y, rule, |x0, y0, x1, y1| {
+            count += self.rect_sum(&self.alive, x0, y0, x1, y1);
+        });
+
+        if is_alive && !rule.include_center {
+            count -= 1;
+        }
+        count
+    }
+
+    // Promedio de los colores de la vecindad: circular para el matiz, simple para la
+    // saturación y el brillo (None si no hay vecinos con color)
+    pub fn average_color(&self, x: usize, y: usize, rule: &Rule) -> Option<Hsv> {
+        let mut colored = 0;
+        let mut cos = 0.0;
+        let mut sin = 0.0;
+        let mut saturation = 0.0;
+        let mut value = 0.0;
+        self.for_each_rect(x, y, rule, |x0, y0, x1, y1| {
+            colored += self.rect_sum(&self.colored, x0, y0, x1, y1);
+            cos += self.rect_sum(&self.cos, x0, y0, x1, y1);
+            sin += self.rect_sum(&self.sin, x0, y0, x1, y1);
+            saturation += self.rect_sum(&self.saturation, x0, y0, x1, y1);
+            value += self.rect_sum(&self.value, x0, y0, x1, y1);
+        });
+
+        if colored == 0 {
+            return None;
+        }
+        Some(Hsv::new(
+            Hue::new((sin.atan2(cos) / (2.0 * PI)) as f32),
+            (saturation / colored as f64) as f32,
+            (value / colored as f64) as f32,
+        ))
+    }
+}